@@ -0,0 +1,94 @@
+use rand::prelude::*;
+
+// Which re-optimised kick becomes the next starting point.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IlsAcceptance {
+    // Only strictly better local optima.
+    Better,
+    // Every local optimum.
+    RandomWalk,
+    // Better ones, restarting from a random permutation after this many
+    // kicks in a row fail to improve.
+    Restart(usize),
+}
+
+// Cuts the tour into A B C D at three random points and reconnects it as
+// A C B D. No 2-opt move undoes this in one step.
+pub fn double_bridge(permutation: &[usize], rng: &mut impl Rng) -> Vec<usize> {
+    let length = permutation.len();
+    if length < 8 {
+        let mut kicked = permutation.to_vec();
+        kicked.shuffle(rng);
+        return kicked;
+    }
+    let mut cuts = rand::seq::index::sample(rng, length - 1, 3).into_vec();
+    cuts.sort_unstable();
+    let (a, b, c) = (cuts[0] + 1, cuts[1] + 1, cuts[2] + 1);
+    let mut kicked = Vec::with_capacity(length);
+    kicked.extend_from_slice(&permutation[..a]);
+    kicked.extend_from_slice(&permutation[b..c]);
+    kicked.extend_from_slice(&permutation[a..b]);
+    kicked.extend_from_slice(&permutation[c..]);
+    kicked
+}
+
+// Descends from `permutation` with `descend` (`local_search` or Lin–Kernighan),
+// then `iterations` times kicks the current local optimum with a double bridge
+// and descends again. Returns the best tour, the steps summed over all descents
+// and the best weight, like `local_search`.
+pub fn iterated_local_search(
+    permutation: Vec<usize>,
+    mut descend: impl FnMut(Vec<usize>) -> (Vec<usize>, usize, usize),
+    iterations: usize,
+    acceptance: IlsAcceptance,
+    rng: &mut impl Rng,
+) -> (Vec<usize>, usize, usize) {
+    let (mut curr, mut steps, mut curr_weight) = descend(permutation);
+    let mut best = curr.clone();
+    let mut best_weight = curr_weight;
+    let mut failures = 0;
+    for _ in 0..iterations {
+        let kicked = double_bridge(&curr, rng);
+        let (candidate, counter, weight) = descend(kicked);
+        steps += counter;
+        if weight < best_weight {
+            best.clone_from(&candidate);
+            best_weight = weight;
+        }
+        match acceptance {
+            IlsAcceptance::Better => {
+                if weight < curr_weight {
+                    curr = candidate;
+                    curr_weight = weight;
+                }
+            }
+            IlsAcceptance::RandomWalk => {
+                curr = candidate;
+                curr_weight = weight;
+            }
+            IlsAcceptance::Restart(limit) => {
+                if weight < curr_weight {
+                    curr = candidate;
+                    curr_weight = weight;
+                    failures = 0;
+                } else {
+                    failures += 1;
+                }
+                if failures >= limit {
+                    let mut restart = curr;
+                    restart.shuffle(rng);
+                    let (restart, counter, weight) = descend(restart);
+                    steps += counter;
+                    if weight < best_weight {
+                        best.clone_from(&restart);
+                        best_weight = weight;
+                    }
+                    curr = restart;
+                    curr_weight = weight;
+                    failures = 0;
+                }
+            }
+        }
+    }
+    (best, steps, best_weight)
+}
@@ -1,37 +1,67 @@
+use std::collections::VecDeque;
 use std::fs::{self, File};
-use std::io::{self, BufRead, Write};
-use std::path::Path;
+use std::io::Write;
 
 use rand::prelude::*;
-use rand::seq::IteratorRandom;
 use rand::SeedableRng;
 use rand_pcg::Pcg64;
 
-type Point = (f32, f32);
+mod ils;
+
+use ils::{iterated_local_search, IlsAcceptance};
+use tsp_core::moves::{insert_segment, insertion_weight, invert_weight, Move};
+use tsp_core::mst::{candidate_prim, dfs_from_point, mst_weight, parent_to_adj_list, MST_CANDIDATES};
+use tsp_core::order::{ArrayTour, TourOrder, TwoLevelList, TWO_LEVEL_THRESHOLD};
+use tsp_core::{candidate_lists, held_karp_bound, instance_to_matrix, is_symmetric, permutation_weight, write_tour, Distance, Instance, LinKernighan, HELD_KARP_ITERATIONS};
 
 fn main() {
+    // The ILS acceptance rule: better, walk or restart (the default).
+    let ils_acceptance = match std::env::args().nth(1).as_deref() {
+        Some("better") => IlsAcceptance::Better,
+        Some("walk") => IlsAcceptance::RandomWalk,
+        _ => IlsAcceptance::Restart(ILS_ITERATIONS / 5),
+    };
+    // The composite and candidate descents take the first improving move
+    // unless asked for the best.
+    let improvement = match std::env::args().nth(2).as_deref() {
+        Some("best") => Improvement::Best,
+        _ => Improvement::First,
+    };
+    fs::create_dir_all("tours").unwrap();
     let mut weight_file = File::create("./ls.csv").unwrap();
-    weight_file.write_all(b"map;mst_weight;dfs_steps;dfs_mean;dfs_min;random_steps;random_mean;random_min;mod_random_steps;mod_random_mean;mod_random_min\n").unwrap();
+    weight_file.write_all(b"map;mst_weight;hk_bound;dfs_steps;dfs_mean;dfs_min;random_steps;random_mean;random_min;dlb_steps;dlb_mean;dlb_min;mod_random_steps;mod_random_mean;mod_random_min;ils_steps;ils_mean;ils_min;composite_steps;composite_mean;composite_min;cand_steps;cand_mean;cand_min;linked_steps;linked_mean;linked_min;lk_steps;lk_mean;lk_min;clk_mean;clk_min\n").unwrap();
     //let paths = fs::read_dir("test_data/").unwrap();
     //for path in paths {
     for path in ["test_data/c.tsp", "test_data/d.tsp", "test_data/e.tsp", "test_data/f.tsp"] {
         //let points = file_to_points(path.unwrap().path());
-        let points = file_to_points(path);
-        let point_count = points.len();
-        let adj_matrix = points_to_matrix(points);
-        let parent = prim(&adj_matrix, point_count);
+        let instance = match Instance::from_file(path) {
+            Ok(instance) => instance,
+            Err(err) => {
+                eprintln!("{path}: {err}");
+                continue;
+            }
+        };
+        let point_count = instance.dimension;
+        let adj_matrix = instance_to_matrix(&instance);
+        let parent = candidate_prim(&adj_matrix, &candidate_lists(&instance, MST_CANDIDATES));
         let mst = parent_to_adj_list(&parent);
         let mst_weight = mst_weight(&parent, &adj_matrix);
+        let mut best_tour: Vec<usize> = Vec::new();
+        let mut best_weight = usize::MAX;
 
-        let mut dfs_min = u64::MAX;
-        let mut dfs_mean = 0_u64;
+        let mut dfs_min = usize::MAX;
+        let mut dfs_mean = 0_usize;
         let mut dfs_steps = 0_usize;
         let mut rng = Pcg64::from_entropy();
         //for _ in 0..((point_count as f32).sqrt() as usize) {
         for _ in 0..100 {
             let start = rng.gen_range(0..point_count);
             let permutation = dfs_from_point(&mst, start);
-            let (_p, counter, w) = local_search(permutation.clone(), &adj_matrix);
+            let (p, counter, w) = local_search(permutation.clone(), &adj_matrix);
+            if w < best_weight {
+                best_weight = w;
+                best_tour = p;
+            }
             dfs_mean += w;
             dfs_steps += counter;
             if dfs_min > w {
@@ -41,16 +71,20 @@ fn main() {
         let dfs_mean = dfs_mean as f64 / (point_count as f64).sqrt();
         let dfs_steps = dfs_steps as f64 / (point_count as f64).sqrt();
         
-        let mut random_min = u64::MAX;
-        let mut random_mean = 0_u64;
+        let mut random_min = usize::MAX;
+        let mut random_mean = 0_usize;
         let mut permutation: Vec<usize> = (0..point_count).collect();
         let mut random_steps = 0_usize;
         //for _ in 0..point_count {
         for _ in 0..100 {
             permutation.shuffle(&mut rng);
             //println!("local search start");
-            let (_p, counter, w) = local_search(permutation.clone(), &adj_matrix);
+            let (p, counter, w) = local_search(permutation.clone(), &adj_matrix);
             //println!("local search end");
+            if w < best_weight {
+                best_weight = w;
+                best_tour = p;
+            }
             random_steps += counter;
             random_mean += w;
             if random_min > w {
@@ -60,17 +94,42 @@ fn main() {
         println!("random end");
         let random_mean = random_mean as f64 / point_count as f64;
         let random_steps = random_steps as f64 / point_count as f64;
+
+        let candidates = candidate_lists(&instance, CANDIDATES);
+        let mut dlb_min = usize::MAX;
+        let mut dlb_mean = 0_usize;
+        let mut dlb_steps = 0_usize;
+        let mut permutation: Vec<usize> = (0..point_count).collect();
+        for _ in 0..DLB_RUNS {
+            permutation.shuffle(&mut rng);
+            let (p, counter, w) = dlb_local_search(permutation.clone(), &adj_matrix, &candidates);
+            if w < best_weight {
+                best_weight = w;
+                best_tour = p;
+            }
+            dlb_steps += counter;
+            dlb_mean += w;
+            if dlb_min > w {
+                dlb_min = w;
+            }
+        }
+        let dlb_mean = dlb_mean as f64 / DLB_RUNS as f64;
+        let dlb_steps = dlb_steps as f64 / DLB_RUNS as f64;
         
-        let mut mod_random_min = u64::MAX;
-        let mut mod_random_mean = 0_u64;
+        let mut mod_random_min = usize::MAX;
+        let mut mod_random_mean = 0_usize;
         let mut permutation: Vec<usize> = (0..point_count).collect();
         let mut mod_random_steps = 0_usize;
         //for _ in 0..point_count {
         for _ in 0..100 {
             permutation.shuffle(&mut rng);
             //println!("local search start");
-            let (_p, counter, w) = faster_local_search(permutation.clone(), &adj_matrix);
+            let (p, counter, w) = faster_local_search(permutation.clone(), &adj_matrix);
             //println!("local search end");
+            if w < best_weight {
+                best_weight = w;
+                best_tour = p;
+            }
             mod_random_steps += counter;
             mod_random_mean += w;
             if mod_random_min > w {
@@ -79,115 +138,153 @@ fn main() {
         }
         let mod_random_mean = mod_random_mean as f64 / point_count as f64;
         let mod_random_steps = mod_random_steps as f64 / point_count as f64;
-        
-        weight_file.write_all(format!("{point_count};{mst_weight};{dfs_steps};{dfs_mean};{dfs_min};{random_steps};{random_mean};{random_min};{mod_random_steps};{mod_random_mean};{mod_random_min}\n").as_bytes()).unwrap();
-        //weight_file.write_all(format!("{point_count};{mst_weight};{dfs_steps};{dfs_mean};{dfs_min};{mod_random_steps};{mod_random_mean};{mod_random_min}\n").as_bytes()).unwrap();
-        //weight_file.write_all(format!("{point_count};{mst_weight};{dfs_steps};{dfs_mean};{dfs_min}\n").as_bytes()).unwrap();
-
-    }
-}
-
-fn mst_weight(tree: &Vec<usize>, adj_matrix: &[Vec<u64>]) -> u64 {
-    let mut s: u64 = 0;
-    for i in 1..(tree.len()) {
-        s += adj_matrix[i][tree[i]] as u64
-    }
-    s
-}
 
-fn read_lines<P>(filename: P) -> io::Result<io::Lines<io::BufReader<File>>>
-where
-    P: AsRef<Path>,
-{
-    let file = File::open(filename)?;
-    Ok(io::BufReader::new(file).lines())
-}
+        let mut ils_min = usize::MAX;
+        let mut ils_mean = 0_usize;
+        let mut ils_steps = 0_usize;
+        for _ in 0..ILS_RUNS {
+            let start = rng.gen_range(0..point_count);
+            let permutation = dfs_from_point(&mst, start);
+            let descend = |p| local_search(p, &adj_matrix);
+            let (p, counter, w) = iterated_local_search(permutation, descend, ILS_ITERATIONS, ils_acceptance, &mut rng);
+            if w < best_weight {
+                best_weight = w;
+                best_tour = p;
+            }
+            ils_steps += counter;
+            ils_mean += w;
+            if ils_min > w {
+                ils_min = w;
+            }
+        }
+        let ils_mean = ils_mean as f64 / ILS_RUNS as f64;
+        let ils_steps = ils_steps as f64 / ILS_RUNS as f64;
 
-fn file_to_points<P>(filename: P) -> Vec<Point>
-where
-    P: AsRef<Path>,
-{
-    let mut points: Vec<Point> = Vec::new();
-    if let Ok(lines) = read_lines(filename) {
-        for line in lines.skip(8).flatten() {
-            if line == "EOF" {
-                break;
+        let mut composite_min = usize::MAX;
+        let mut composite_mean = 0_usize;
+        let mut composite_steps = 0_usize;
+        let mut permutation: Vec<usize> = (0..point_count).collect();
+        for _ in 0..COMPOSITE_RUNS {
+            permutation.shuffle(&mut rng);
+            let (p, counter, w) = composite_local_search(permutation.clone(), &adj_matrix, improvement);
+            if w < best_weight {
+                best_weight = w;
+                best_tour = p;
+            }
+            composite_steps += counter;
+            composite_mean += w;
+            if composite_min > w {
+                composite_min = w;
             }
-            let tmp = line.split_whitespace().collect::<Vec<&str>>();
-            points.push((tmp[1].parse().unwrap(), tmp[2].parse().unwrap()));
         }
-    }
-    points
-}
+        let composite_mean = composite_mean as f64 / COMPOSITE_RUNS as f64;
+        let composite_steps = composite_steps as f64 / COMPOSITE_RUNS as f64;
 
-fn prim(adj_matrix: &[Vec<u64>], point_count: usize) -> Vec<usize> {
-    let mut parent: Vec<usize> = vec![usize::MAX; point_count];
-    let mut key: Vec<u64> = vec![u64::MAX; point_count];
-    let mut mst_set: Vec<bool> = vec![false; point_count];
+        let mut linked_min = usize::MAX;
+        let mut linked_mean = 0_usize;
+        let mut linked_steps = 0_usize;
+        for _ in 0..DLB_RUNS {
+            let start = rng.gen_range(0..point_count);
+            let permutation = dfs_from_point(&mst, start);
+            let (p, counter, w) = linked_local_search(permutation, &adj_matrix, &candidates);
+            if w < best_weight {
+                best_weight = w;
+                best_tour = p;
+            }
+            linked_steps += counter;
+            linked_mean += w;
+            if linked_min > w {
+                linked_min = w;
+            }
+        }
+        let linked_mean = linked_mean as f64 / DLB_RUNS as f64;
+        let linked_steps = linked_steps as f64 / DLB_RUNS as f64;
 
-    key[0] = 0;
+        let mut cand_min = usize::MAX;
+        let mut cand_mean = 0_usize;
+        let mut cand_steps = 0_usize;
+        let mut permutation: Vec<usize> = (0..point_count).collect();
+        for _ in 0..COMPOSITE_RUNS {
+            permutation.shuffle(&mut rng);
+            let (p, counter, w) = candidate_local_search(permutation.clone(), &adj_matrix, &candidates, improvement);
+            if w < best_weight {
+                best_weight = w;
+                best_tour = p;
+            }
+            cand_steps += counter;
+            cand_mean += w;
+            if cand_min > w {
+                cand_min = w;
+            }
+        }
+        let cand_mean = cand_mean as f64 / COMPOSITE_RUNS as f64;
+        let cand_steps = cand_steps as f64 / COMPOSITE_RUNS as f64;
 
-    for _ in 0..(point_count - 1) {
-        let u = min_key(&key, &mst_set, point_count);
-        mst_set[u] = true;
-        for v in 0..point_count {
-            if adj_matrix[u][v] != 0 && !mst_set[v] && adj_matrix[u][v] < key[v] {
-                parent[v] = u;
-                key[v] = adj_matrix[u][v];
+        // Lin–Kernighan from MST tours on its own, then chained by the ILS.
+        let lk = LinKernighan::with_candidates(&adj_matrix, candidates);
+        let mut lk_min = usize::MAX;
+        let mut lk_mean = 0_usize;
+        let mut lk_steps = 0_usize;
+        let mut clk_min = usize::MAX;
+        let mut clk_mean = 0_usize;
+        let lk_kicks = (LK_KICK_BUDGET / point_count).max(10);
+        for _ in 0..ILS_RUNS {
+            let start = rng.gen_range(0..point_count);
+            let permutation = dfs_from_point(&mst, start);
+            let (p, counter, w) = lk.improve(permutation.clone(), &adj_matrix);
+            if w < best_weight {
+                best_weight = w;
+                best_tour = p;
+            }
+            lk_steps += counter;
+            lk_mean += w;
+            if lk_min > w {
+                lk_min = w;
+            }
+            let descend = |p| lk.improve(p, &adj_matrix);
+            let (p, _counter, w) = iterated_local_search(permutation, descend, lk_kicks, ils_acceptance, &mut rng);
+            if w < best_weight {
+                best_weight = w;
+                best_tour = p;
+            }
+            clk_mean += w;
+            if clk_min > w {
+                clk_min = w;
             }
         }
-    }
+        let lk_mean = lk_mean as f64 / ILS_RUNS as f64;
+        let lk_steps = lk_steps as f64 / ILS_RUNS as f64;
+        let clk_mean = clk_mean as f64 / ILS_RUNS as f64;
+        let hk_bound = held_karp_bound(&adj_matrix, best_weight, HELD_KARP_ITERATIONS);
 
-    parent
-}
+        weight_file.write_all(format!("{point_count};{mst_weight};{hk_bound};{dfs_steps};{dfs_mean};{dfs_min};{random_steps};{random_mean};{random_min};{dlb_steps};{dlb_mean};{dlb_min};{mod_random_steps};{mod_random_mean};{mod_random_min};{ils_steps};{ils_mean};{ils_min};{composite_steps};{composite_mean};{composite_min};{cand_steps};{cand_mean};{cand_min};{linked_steps};{linked_mean};{linked_min};{lk_steps};{lk_mean};{lk_min};{clk_mean};{clk_min}\n").as_bytes()).unwrap();
+        //weight_file.write_all(format!("{point_count};{mst_weight};{dfs_steps};{dfs_mean};{dfs_min};{mod_random_steps};{mod_random_mean};{mod_random_min}\n").as_bytes()).unwrap();
+        //weight_file.write_all(format!("{point_count};{mst_weight};{dfs_steps};{dfs_mean};{dfs_min}\n").as_bytes()).unwrap();
+        write_tour(format!("tours/{}.ls.tour", instance.name), &instance.name, &format!("Length {best_weight}"), &best_tour).unwrap();
 
-fn min_key(key: &[u64], mst_set: &[bool], point_count: usize) -> usize {
-    let mut min = u64::MAX;
-    let mut min_index = 0;
-    for v in 0..point_count {
-        if !mst_set[v] && key[v] < min {
-            min = key[v];
-            min_index = v;
-        }
     }
-    min_index
 }
 
-fn parent_to_adj_list(parent: &Vec<usize>) -> Vec<Vec<usize>> {
-    let mut adj_list: Vec<Vec<usize>> = vec![Vec::new(); parent.len()];
-    for (u, v) in parent.iter().enumerate().skip(1) {
-        adj_list[u].push(*v);
-        adj_list[*v].push(u);
-    }
-    adj_list
-}
+// Kicks per iterated local search run, and runs per map of the slower searches.
+const ILS_ITERATIONS: usize = 100;
+const ILS_RUNS: usize = 10;
+const COMPOSITE_RUNS: usize = 10;
+// Starts for the don't-look bits descents, which are cheap enough for many.
+const DLB_RUNS: usize = 1000;
+// Kicks times cities per chained Lin–Kernighan run: every kick re-runs LK from
+// all cities, so larger maps get fewer kicks (67 on c, 38 on f).
+const LK_KICK_BUDGET: usize = 100_000;
 
-fn dfs_from_point(graph: &[Vec<usize>], start: usize) -> Vec<usize> {
-    let mut visited: Vec<usize> = Vec::new();
-    let mut traversal: Vec<usize> = Vec::new();
-    let mut stack: Vec<usize> = Vec::new();
-    visited.push(start);
-    stack.push(start);
-    while let Some(node) = stack.pop() {
-        traversal.push(node);
-        for j in &graph[node] {
-            if !visited.contains(j) {
-                visited.push(*j);
-                stack.push(*j);
-            }
-        }
+fn local_search<D: Distance + ?Sized>(permutation: Vec<usize>, adj_matrix: &D) -> (Vec<usize>, usize, usize) {
+    if !is_symmetric(adj_matrix) {
+        return atsp_local_search(permutation, adj_matrix);
     }
-    traversal
-}
-
-fn local_search(permutation: Vec<usize>, adj_matrix: &[Vec<u64>]) -> (Vec<usize>, usize, u64) {
     let mut curr_weight = permutation_weight(&permutation, adj_matrix);
     let mut curr = permutation.clone();
     let mut counter = 0;
     loop {
         counter += 1;
-        let neighborhood: Vec<(usize, usize, u64)> = get_neighborhood(&curr, adj_matrix, curr_weight
-        );
+        let neighborhood: Vec<(usize, usize, usize)> = get_neighborhood(&curr, adj_matrix, curr_weight, true);
         let candidate = neighborhood.iter().min_by_key(|a| a.2).unwrap();
         //let candidate = get_candidate(&permutation, adj_matrix, curr_weight);
         //println!("exp:{:?} got:{:?}", permutation_weight(&invert(curr.clone(), candidate.0, candidate.1), adj_matrix), candidate.2);
@@ -203,13 +300,60 @@ fn local_search(permutation: Vec<usize>, adj_matrix: &[Vec<u64>]) -> (Vec<usize>
     (curr, counter, curr_weight)
 }
 
-fn faster_local_search(permutation: Vec<usize>, adj_matrix: &[Vec<u64>]) -> (Vec<usize>, usize, u64) {
+// Longest segment moved by or-opt before falling back to full segment insertion.
+const OR_OPT_SEGMENT: usize = 3;
+
+// Best-improvement descent for asymmetric matrices. Inversions turn their
+// inner edges around, so or-opt segment moves are searched alongside them and
+// 3-opt segment insertion of any length is tried once both are exhausted.
+fn atsp_local_search<D: Distance + ?Sized>(permutation: Vec<usize>, adj_matrix: &D) -> (Vec<usize>, usize, usize) {
+    let mut curr_weight = permutation_weight(&permutation, adj_matrix);
+    let mut curr = permutation;
+    let length = curr.len();
+    let mut counter = 0;
+    loop {
+        counter += 1;
+        let inversion = get_neighborhood(&curr, adj_matrix, curr_weight, false)
+            .into_iter()
+            .min_by_key(|a| a.2)
+            .filter(|a| a.2 < curr_weight);
+        let mut insertion = get_insertion_neighborhood(&curr, adj_matrix, curr_weight, OR_OPT_SEGMENT)
+            .into_iter()
+            .min_by_key(|a| a.3)
+            .filter(|a| a.3 < curr_weight);
+        if inversion.is_none() && insertion.is_none() {
+            insertion = get_insertion_neighborhood(&curr, adj_matrix, curr_weight, length)
+                .into_iter()
+                .min_by_key(|a| a.3)
+                .filter(|a| a.3 < curr_weight);
+        }
+        match (inversion, insertion) {
+            (Some((i, j, w)), Some((.., v))) if w <= v => {
+                curr[i..=j].reverse();
+                curr_weight = w;
+            }
+            (Some((i, j, w)), None) => {
+                curr[i..=j].reverse();
+                curr_weight = w;
+            }
+            (_, Some((i, j, k, w))) => {
+                insert_segment(&mut curr, i, j, k);
+                curr_weight = w;
+            }
+            (None, None) => break,
+        }
+    }
+    (curr, counter, curr_weight)
+}
+
+fn faster_local_search<D: Distance + ?Sized>(permutation: Vec<usize>, adj_matrix: &D) -> (Vec<usize>, usize, usize) {
+    let symmetric = is_symmetric(adj_matrix);
     let mut curr_weight = permutation_weight(&permutation, adj_matrix);
     let mut curr = permutation.clone();
     let mut counter = 0;
     loop {
         counter += 1;
-        let neighborhood: Vec<(usize, usize, u64)> = get_faster_neighborhood(&curr, adj_matrix);
+        let neighborhood: Vec<(usize, usize, usize)> = get_faster_neighborhood(&curr, adj_matrix, symmetric);
         let candidate = neighborhood.iter().min_by_key(|a| a.2).unwrap();
         if candidate.2 >= curr_weight {
             break;
@@ -220,95 +364,388 @@ fn faster_local_search(permutation: Vec<usize>, adj_matrix: &[Vec<u64>]) -> (Vec
     (curr, counter, curr_weight)
 }
 
-fn get_neighborhood(
-    permutation: &Vec<usize>,
-    adj_matrix: &[Vec<u64>],
-    weight: u64,
-) -> Vec<(usize, usize, u64)> {
-    let mut neighborhood: Vec<(usize, usize, u64)> = Vec::new();
+// First-improvement 2-opt. Cities wait in a queue; a city leaves it (its
+// don't-look bits are set) once no inversion adding an edge to one of its
+// `candidates` improves, and comes back when a move changes one of its edges.
+// A city costs O(k) move evaluations, not O(n).
+fn dlb_local_search<D: Distance + ?Sized>(
+    permutation: Vec<usize>,
+    adj_matrix: &D,
+    candidates: &[Vec<usize>],
+) -> (Vec<usize>, usize, usize) {
+    let symmetric = is_symmetric(adj_matrix);
     let length = permutation.len();
-    for diff in 1..(length/2) {
+    let mut curr_weight = permutation_weight(&permutation, adj_matrix);
+    let mut curr = permutation;
+    let mut pos = vec![0; length];
+    for (i, &city) in curr.iter().enumerate() {
+        pos[city] = i;
+    }
+    let mut queue: VecDeque<usize> = curr.iter().copied().collect();
+    let mut queued = vec![true; length];
+    let mut counter = 1;
+    while let Some(city) = queue.pop_front() {
+        queued[city] = false;
+        let p = pos[city];
+        let improves = |i: usize, j: usize| {
+            let w = invert_weight(&curr, adj_matrix, i, j, curr_weight, symmetric);
+            (w < curr_weight).then_some((i, j, w))
+        };
+        // Joining `city` to a candidate `c` replaces the edges leaving both
+        // (city-succ(city), c-succ(c)) or those entering both.
+        let found = candidates[city].iter().find_map(|&c| {
+            let q = pos[c];
+            let (lo, hi) = (p.min(q), p.max(q));
+            if hi - lo < 2 {
+                return None;
+            }
+            improves(lo + 1, hi).or_else(|| improves(lo, hi - 1))
+        });
+        let Some((i, j, w)) = found else {
+            continue;
+        };
+        curr[i..=j].reverse();
+        for (k, &city) in curr.iter().enumerate().take(j + 1).skip(i) {
+            pos[city] = k;
+        }
+        curr_weight = w;
+        counter += 1;
+        for k in [(i + length - 1) % length, i, j, (j + 1) % length] {
+            if !queued[curr[k]] {
+                queued[curr[k]] = true;
+                queue.push_back(curr[k]);
+            }
+        }
+    }
+    (curr, counter, curr_weight)
+}
+
+fn get_neighborhood<D: Distance + ?Sized>(
+    permutation: &[usize],
+    adj_matrix: &D,
+    weight: usize,
+    symmetric: bool,
+) -> Vec<(usize, usize, usize)> {
+    let mut neighborhood: Vec<(usize, usize, usize)> = Vec::new();
+    let length = permutation.len();
+    for diff in 1..max_inversion(length, symmetric) {
         for j in diff..length {
             neighborhood.push((
                 j - diff,
                 j,
-                invert_weight(permutation, adj_matrix, j - diff, j, weight),
+                invert_weight(permutation, adj_matrix, j - diff, j, weight, symmetric),
             ));
         }
     }
     neighborhood
 }
 
-fn get_faster_neighborhood(
-    permutation: &Vec<usize>,
-    adj_matrix: &[Vec<u64>],
-) -> Vec<(usize, usize, u64)> {
-    let mut neighborhood: Vec<(usize, usize, u64)> = Vec::new();
+// A symmetric tour costs the same in both directions, so inverting more than
+// half of it is the same as inverting the rest. Asymmetric ones need them all.
+fn max_inversion(length: usize, symmetric: bool) -> usize {
+    if symmetric {
+        length / 2
+    } else {
+        length - 1
+    }
+}
+
+// Moves of the segment `i..=j` (at most `max_segment` long) between positions
+// `k` and `k + 1`, keeping its orientation; as (i, j, k, new weight).
+fn get_insertion_neighborhood<D: Distance + ?Sized>(
+    permutation: &[usize],
+    adj_matrix: &D,
+    weight: usize,
+    max_segment: usize,
+) -> Vec<(usize, usize, usize, usize)> {
+    let mut neighborhood: Vec<(usize, usize, usize, usize)> = Vec::new();
+    let length = permutation.len();
+    for segment in 1..=max_segment.min(length.saturating_sub(2)) {
+        for i in 0..=(length - segment) {
+            let j = i + segment - 1;
+            let pre = (i + length - 1) % length;
+            for k in (0..length).filter(|&k| k != pre && (k < i || k > j)) {
+                neighborhood.push((i, j, k, insertion_weight(permutation, adj_matrix, i, j, k, weight)));
+            }
+        }
+    }
+    neighborhood
+}
+
+fn get_faster_neighborhood<D: Distance + ?Sized>(
+    permutation: &[usize],
+    adj_matrix: &D,
+    symmetric: bool,
+) -> Vec<(usize, usize, usize)> {
+    let mut neighborhood: Vec<(usize, usize, usize)> = Vec::new();
     let length = permutation.len();
     let weight = permutation_weight(permutation, adj_matrix);
-    let mut candidates = Vec::new();
+    let max_diff = max_inversion(length, symmetric);
+    if max_diff < 2 {
+        return neighborhood;
+    }
     let mut rng = Pcg64::from_entropy();
-    for diff in 1..(length/2) {
-        for j in diff..length {
-            candidates.push((j - diff, j));
+    // Draw the inversions directly instead of listing all O(n²) of them.
+    while neighborhood.len() < length {
+        let i = rng.gen_range(0..length);
+        let j = rng.gen_range(0..length);
+        if i < j && j - i < max_diff {
+            neighborhood.push((i, j, invert_weight(permutation, adj_matrix, i, j, weight, symmetric)));
         }
     }
-    for (i, j) in candidates.iter().choose_multiple(&mut rng, length) {
-        neighborhood.push((
-            *i,
-            *j,
-            invert_weight(permutation, adj_matrix, *i, *j, weight),
-        ));
-    }
     neighborhood
 }
 
-fn invert_weight(
-    permutation: &Vec<usize>,
-    adj_matrix: &[Vec<u64>],
-    i: usize,
-    j: usize,
-    weight: u64,
-) -> u64 {
-    let last = permutation.len() - 1;
-    let pre = i.checked_sub(1).unwrap_or(last);
-    let post = (j + 1) % permutation.len();
-    weight - adj_matrix[permutation[i]][permutation[pre]] - adj_matrix[permutation[j]][permutation[post]] + adj_matrix[permutation[j]][permutation[pre]] + adj_matrix[permutation[i]][permutation[post]]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Improvement {
+    First,
+    Best,
 }
 
-fn permutation_weight(permutation: &[usize], adj_matrix: &[Vec<u64>]) -> u64 {
-    let mut s: u64 = 0;
-    let mut prev = permutation[0];
-    for cur in permutation.iter().skip(1) {
-        s += adj_matrix[prev][*cur] as u64;
-        prev = *cur;
+// Longest segment moved by the 3-opt fallback of the composite neighborhood.
+const THREE_OPT_SEGMENT: usize = 30;
+
+// Descent over 2-opt inversions and or-opt moves of up to `OR_OPT_SEGMENT`
+// cities, kept or reversed. Once none of them improves, segments of up to
+// `THREE_OPT_SEGMENT` cities are moved as well (3-opt).
+fn composite_local_search<D: Distance + ?Sized>(
+    permutation: Vec<usize>,
+    adj_matrix: &D,
+    improvement: Improvement,
+) -> (Vec<usize>, usize, usize) {
+    let symmetric = is_symmetric(adj_matrix);
+    let length = permutation.len();
+    let mut curr_weight = permutation_weight(&permutation, adj_matrix);
+    let mut curr = permutation;
+    let mut counter = 0;
+    loop {
+        counter += 1;
+        let composite = two_opt_moves(length, symmetric).chain(segment_moves(length, 1, OR_OPT_SEGMENT));
+        let candidate = pick_move(composite, &curr, adj_matrix, curr_weight, symmetric, improvement).or_else(|| {
+            let three_opt = segment_moves(length, OR_OPT_SEGMENT + 1, THREE_OPT_SEGMENT);
+            pick_move(three_opt, &curr, adj_matrix, curr_weight, symmetric, improvement)
+        });
+        let Some((mv, w)) = candidate else {
+            break;
+        };
+        mv.apply(&mut curr);
+        curr_weight = w;
     }
-    s += *adj_matrix[0].last().unwrap() as u64;
-    s
+    (curr, counter, curr_weight)
+}
+
+fn pick_move<D: Distance + ?Sized>(
+    moves: impl Iterator<Item = Move>,
+    permutation: &[usize],
+    adj_matrix: &D,
+    weight: usize,
+    symmetric: bool,
+    improvement: Improvement,
+) -> Option<(Move, usize)> {
+    let mut improving = moves
+        .map(|mv| (mv, mv.weight(permutation, adj_matrix, weight, symmetric)))
+        .filter(|&(_, w)| w < weight);
+    match improvement {
+        Improvement::First => improving.next(),
+        Improvement::Best => improving.min_by_key(|&(_, w)| w),
+    }
+}
+
+fn two_opt_moves(length: usize, symmetric: bool) -> impl Iterator<Item = Move> {
+    (1..max_inversion(length, symmetric)).flat_map(move |diff| (diff..length).map(move |j| Move::Reversal(j - diff, j)))
 }
 
-fn points_to_matrix(points: Vec<(f32, f32)>) -> Vec<Vec<u64>> {
-    let point_count = points.len();
-    let mut adj_matrix: Vec<Vec<u64>> = vec![vec![0; point_count]; point_count];
-    for i in 0..point_count {
-        for j in i..point_count {
-            if j != i {
-                let p1 = points[i];
-                let p2 = points[j];
-                let dist = (((p1.0 - p2.0).powi(2) + (p1.1 - p2.1).powi(2)).sqrt()).round() as u64;
-                adj_matrix[i][j] = dist;
-                adj_matrix[j][i] = dist;
+// Moves of segments of `min_segment..=max_segment` cities between positions
+// `k` and `k + 1`, both kept and reversed.
+fn segment_moves(length: usize, min_segment: usize, max_segment: usize) -> impl Iterator<Item = Move> {
+    (min_segment..=max_segment.min(length.saturating_sub(2))).flat_map(move |segment| {
+        (0..=(length - segment)).flat_map(move |i| {
+            let j = i + segment - 1;
+            let pre = (i + length - 1) % length;
+            (0..length)
+                .filter(move |&k| k != pre && (k < i || k > j))
+                .flat_map(move |k| {
+                    let reversed = (i != j).then_some(Move::ReversedInsertion { i, j, k });
+                    std::iter::once(Move::Insertion { i, j, k }).chain(reversed)
+                })
+        })
+    })
+}
+
+// Cities in each candidate list of the pruned local search.
+const CANDIDATES: usize = 8;
+
+// Descent over the 2-opt and or-opt moves that add an edge between a city and
+// one of its `candidates`, so a step costs O(n * k) instead of O(n²).
+fn candidate_local_search<D: Distance + ?Sized>(
+    permutation: Vec<usize>,
+    adj_matrix: &D,
+    candidates: &[Vec<usize>],
+    improvement: Improvement,
+) -> (Vec<usize>, usize, usize) {
+    let symmetric = is_symmetric(adj_matrix);
+    let length = permutation.len();
+    let mut curr_weight = permutation_weight(&permutation, adj_matrix);
+    let mut curr = permutation;
+    let mut pos = vec![0; length];
+    for (i, &city) in curr.iter().enumerate() {
+        pos[city] = i;
+    }
+    let mut counter = 0;
+    // First improvement keeps going round the cities and stops after a whole
+    // round without a move.
+    let mut city = 0;
+    let mut idle = 0;
+    loop {
+        counter += 1;
+        let candidate = match improvement {
+            Improvement::First => {
+                let mut found = None;
+                while found.is_none() && idle < length {
+                    let moves = candidate_moves(&curr, &pos, candidates, pos[city]);
+                    found = pick_move(moves.into_iter(), &curr, adj_matrix, curr_weight, symmetric, improvement);
+                    city = (city + 1) % length;
+                    idle += 1;
+                }
+                idle = 0;
+                found
             }
+            Improvement::Best => {
+                let moves = (0..length).flat_map(|i| candidate_moves(&curr, &pos, candidates, i));
+                pick_move(moves, &curr, adj_matrix, curr_weight, symmetric, improvement)
+            }
+        };
+        let Some((mv, w)) = candidate else {
+            break;
+        };
+        mv.apply(&mut curr);
+        let (lo, hi) = match mv {
+            Move::Swap(i, j) | Move::Reversal(i, j) => (i, j),
+            Move::Insertion { i, j, k } | Move::ReversedInsertion { i, j, k } => {
+                if k > j {
+                    (i, k)
+                } else {
+                    (k + 1, j)
+                }
+            }
+        };
+        for (k, &city) in curr.iter().enumerate().take(hi + 1).skip(lo) {
+            pos[city] = k;
         }
+        curr_weight = w;
     }
-    //println!("{:?}", adj_matrix);
-    adj_matrix
+    (curr, counter, curr_weight)
 }
 
+// Moves adding an edge from `permutation[i]` to one of its candidates: both
+// 2-opt inversions and or-opt moves of the segments starting or ending at `i`,
+// kept or reversed.
+fn candidate_moves(permutation: &[usize], pos: &[usize], candidates: &[Vec<usize>], i: usize) -> Vec<Move> {
+    let length = permutation.len();
+    let mut moves = Vec::new();
+    for &c in &candidates[permutation[i]] {
+        let p = pos[c];
+        let before = (p + length - 1) % length;
+        let (lo, hi) = (i.min(p), i.max(p));
+        if hi - lo >= 2 {
+            moves.push(Move::Reversal(lo + 1, hi));
+            moves.push(Move::Reversal(lo, hi - 1));
+        }
+        for segment in 1..=OR_OPT_SEGMENT.min(length.saturating_sub(2)) {
+            if i + segment <= length {
+                let j = i + segment - 1;
+                push_segment_move(&mut moves, length, i, j, p, false);
+                push_segment_move(&mut moves, length, i, j, before, true);
+            }
+            if segment > 1 && i + 1 >= segment {
+                let start = i + 1 - segment;
+                push_segment_move(&mut moves, length, start, i, before, false);
+                push_segment_move(&mut moves, length, start, i, p, true);
+            }
+        }
+    }
+    moves
+}
+
+fn push_segment_move(moves: &mut Vec<Move>, length: usize, i: usize, j: usize, k: usize, reversed: bool) {
+    if k == (i + length - 1) % length || (i..=j).contains(&k) {
+        return;
+    }
+    if !reversed {
+        moves.push(Move::Insertion { i, j, k });
+    } else if i != j {
+        moves.push(Move::ReversedInsertion { i, j, k });
+    }
+}
+
+// Neighbour-list 2-opt with don't-look bits on a `TourOrder`: a
+// `TwoLevelList` above `TWO_LEVEL_THRESHOLD` cities, where O(n) array
+// reversals would dominate. Asymmetric matrices go to `dlb_local_search`.
+fn linked_local_search<D: Distance + ?Sized>(
+    permutation: Vec<usize>,
+    adj_matrix: &D,
+    candidates: &[Vec<usize>],
+) -> (Vec<usize>, usize, usize) {
+    if !is_symmetric(adj_matrix) {
+        return dlb_local_search(permutation, adj_matrix, candidates);
+    }
+    let (tour, counter) = if permutation.len() > TWO_LEVEL_THRESHOLD {
+        two_opt_on(TwoLevelList::new(&permutation), adj_matrix, candidates)
+    } else {
+        two_opt_on(ArrayTour::new(&permutation), adj_matrix, candidates)
+    };
+    let weight = permutation_weight(&tour, adj_matrix);
+    (tour, counter, weight)
+}
+
+fn two_opt_on<T: TourOrder, D: Distance + ?Sized>(mut tour: T, adj_matrix: &D, candidates: &[Vec<usize>]) -> (Vec<usize>, usize) {
+    let order = tour.order();
+    let mut queued = vec![true; order.len()];
+    let mut queue: VecDeque<usize> = order.into_iter().collect();
+    let mut counter = 1;
+    while let Some(a) = queue.pop_front() {
+        queued[a] = false;
+        let mut moved = None;
+        'search: for forward in [true, false] {
+            let b = if forward { tour.next(a) } else { tour.prev(a) };
+            for &c in &candidates[a] {
+                if adj_matrix.dist(a, c) >= adj_matrix.dist(a, b) {
+                    break;
+                }
+                let d = if forward { tour.next(c) } else { tour.prev(c) };
+                if c == b || d == a {
+                    continue;
+                }
+                if adj_matrix.dist(a, c) + adj_matrix.dist(b, d) < adj_matrix.dist(a, b) + adj_matrix.dist(c, d) {
+                    // a b .. c d becomes a c .. b d, read in the same direction.
+                    if forward {
+                        tour.flip(b, c);
+                    } else {
+                        tour.flip(a, d);
+                    }
+                    moved = Some([a, b, c, d]);
+                    break 'search;
+                }
+            }
+        }
+        if let Some(cities) = moved {
+            counter += 1;
+            for city in cities {
+                if !queued[city] {
+                    queued[city] = true;
+                    queue.push_back(city);
+                }
+            }
+        }
+    }
+    (tour.order(), counter)
+}
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use tsp_core::mst::prim;
+    use tsp_core::nearest_neighbours;
 
     #[test]
     fn first_task_test() {
@@ -317,13 +754,13 @@ mod tests {
             "test_data/2.tsp",
             "test_data/3.tsp",
         ] {
-            let points = file_to_points(path);
-            let point_count = points.len();
-            let adj_matrix = points_to_matrix(points);
+            let instance = Instance::from_file(path).unwrap();
+            let point_count = instance.dimension;
+            let adj_matrix = instance_to_matrix(&instance);
             let parent = prim(&adj_matrix, point_count);
             let mst = parent_to_adj_list(&parent);
-            let mut dfs_min = u64::MAX;
-            let mut dfs_mean = 0_u64;
+            let mut dfs_min = usize::MAX;
+            let mut dfs_mean = 0_usize;
             let mut dfs_steps = 0_usize;
             let mut rng = Pcg64::from_entropy();
             for _ in 0..((point_count as f32).sqrt() as usize) {
@@ -349,11 +786,11 @@ mod tests {
             "test_data/2.tsp",
             "test_data/3.tsp",
         ] {
-            let points = file_to_points(path);
-            let point_count = points.len();
-            let adj_matrix = points_to_matrix(points);
-            let mut random_min = u64::MAX;
-            let mut random_mean = 0_u64;
+            let instance = Instance::from_file(path).unwrap();
+            let point_count = instance.dimension;
+            let adj_matrix = instance_to_matrix(&instance);
+            let mut random_min = usize::MAX;
+            let mut random_mean = 0_usize;
             let mut permutation: Vec<usize> = (0..point_count).collect();
             let mut random_steps = 0_usize;
             let mut rng = Pcg64::from_entropy();
@@ -380,11 +817,11 @@ mod tests {
             "test_data/2.tsp",
             "test_data/3.tsp",
         ] {
-            let points = file_to_points(path);
-            let point_count = points.len();
-            let adj_matrix = points_to_matrix(points);
-            let mut random_min = u64::MAX;
-            let mut random_mean = 0_u64;
+            let instance = Instance::from_file(path).unwrap();
+            let point_count = instance.dimension;
+            let adj_matrix = instance_to_matrix(&instance);
+            let mut random_min = usize::MAX;
+            let mut random_mean = 0_usize;
             let mut permutation: Vec<usize> = (0..point_count).collect();
             let mut random_steps = 0_usize;
             let mut rng = Pcg64::from_entropy();
@@ -404,4 +841,234 @@ mod tests {
             println!("{random_min}, {random_mean}, {random_steps}");
         }
     }
+    fn cycle_weight(permutation: &[usize], adj_matrix: &[Vec<usize>]) -> usize {
+        (0..permutation.len())
+            .map(|k| adj_matrix[permutation[k]][permutation[(k + 1) % permutation.len()]])
+            .sum()
+    }
+
+    fn atsp_matrix() -> Vec<Vec<usize>> {
+        let data = "NAME : atsp6\nTYPE : ATSP\nDIMENSION : 6\nEDGE_WEIGHT_TYPE : EXPLICIT\n\
+                    EDGE_WEIGHT_FORMAT : FULL_MATRIX\nEDGE_WEIGHT_SECTION\n\
+                    9999 3 17 25 8 40\n21 9999 4 33 12 9\n6 30 9999 2 27 15\n\
+                    14 8 35 9999 5 22\n31 19 7 26 9999 1\n2 24 13 11 38 9999\nEOF\n";
+        instance_to_matrix(&Instance::parse(data.as_bytes()).unwrap())
+    }
+
+    #[test]
+    fn atsp_move_weights_test() {
+        let adj_matrix = atsp_matrix();
+        assert!(!is_symmetric(&adj_matrix));
+        let permutation = vec![2, 0, 5, 3, 1, 4];
+        let weight = cycle_weight(&permutation, &adj_matrix);
+        for (i, j, w) in get_neighborhood(&permutation, &adj_matrix, weight, false) {
+            let mut moved = permutation.clone();
+            moved[i..=j].reverse();
+            assert_eq!(w, cycle_weight(&moved, &adj_matrix), "inversion {i}..={j}");
+        }
+        for (i, j, k, w) in get_insertion_neighborhood(&permutation, &adj_matrix, weight, 6) {
+            let mut moved = permutation.clone();
+            insert_segment(&mut moved, i, j, k);
+            assert_eq!(w, cycle_weight(&moved, &adj_matrix), "insertion {i}..={j} after {k}");
+        }
+    }
+
+    #[test]
+    fn atsp_local_search_test() {
+        let adj_matrix = atsp_matrix();
+        let mut permutation: Vec<usize> = (0..6).collect();
+        let mut rng = Pcg64::seed_from_u64(1);
+        for _ in 0..20 {
+            permutation.shuffle(&mut rng);
+            let (p, _counter, w) = local_search(permutation.clone(), &adj_matrix);
+            let start = permutation_weight(&permutation, &adj_matrix);
+            assert_eq!(start - w, cycle_weight(&permutation, &adj_matrix) - cycle_weight(&p, &adj_matrix));
+            let best = cycle_weight(&p, &adj_matrix);
+            assert!(get_neighborhood(&p, &adj_matrix, best, false).iter().all(|a| a.2 >= best));
+            assert!(get_insertion_neighborhood(&p, &adj_matrix, best, 6).iter().all(|a| a.3 >= best));
+        }
+    }
+
+    #[test]
+    fn double_bridge_test() {
+        let permutation: Vec<usize> = (0..20).collect();
+        let mut rng = Pcg64::seed_from_u64(1);
+        for _ in 0..50 {
+            let kicked = ils::double_bridge(&permutation, &mut rng);
+            let mut sorted = kicked.clone();
+            sorted.sort();
+            assert_eq!(sorted, permutation);
+            // Four segments keep their inner order, so exactly three
+            // neighbours change.
+            let broken = kicked.windows(2).filter(|w| w[1] != w[0] + 1).count();
+            assert_eq!(broken, 3);
+        }
+    }
+
+    #[test]
+    fn ils_test() {
+        let instance = Instance::from_file("test_data/1.tsp").unwrap();
+        let adj_matrix = instance_to_matrix(&instance);
+        let mut rng = Pcg64::seed_from_u64(1);
+        let mut permutation: Vec<usize> = (0..instance.dimension).collect();
+        permutation.shuffle(&mut rng);
+        let (_, descent_steps, descent) = local_search(permutation.clone(), &adj_matrix);
+        for acceptance in [IlsAcceptance::Better, IlsAcceptance::RandomWalk, IlsAcceptance::Restart(3)] {
+            let (p, steps, w) = iterated_local_search(permutation.clone(), |p| local_search(p, &adj_matrix), 10, acceptance, &mut rng);
+            assert_eq!(w, permutation_weight(&p, &adj_matrix));
+            assert!(w <= descent);
+            assert!(steps > descent_steps);
+        }
+    }
+    #[test]
+    fn composite_local_search_test() {
+        let instance = Instance::from_file("test_data/1.tsp").unwrap();
+        let adj_matrix = instance_to_matrix(&instance);
+        let length = instance.dimension;
+        let mut rng = Pcg64::seed_from_u64(1);
+        let mut permutation: Vec<usize> = (0..length).collect();
+        permutation.shuffle(&mut rng);
+        for improvement in [Improvement::First, Improvement::Best] {
+            let (p, _counter, w) = composite_local_search(permutation.clone(), &adj_matrix, improvement);
+            assert_eq!(w, permutation_weight(&p, &adj_matrix));
+            let moves = two_opt_moves(length, true).chain(segment_moves(length, 1, THREE_OPT_SEGMENT));
+            assert!(pick_move(moves, &p, &adj_matrix, w, true, Improvement::First).is_none());
+        }
+    }
+
+    #[test]
+    fn atsp_composite_local_search_test() {
+        let adj_matrix = atsp_matrix();
+        let mut permutation: Vec<usize> = (0..6).collect();
+        let mut rng = Pcg64::seed_from_u64(1);
+        for _ in 0..20 {
+            permutation.shuffle(&mut rng);
+            let (p, _counter, w) = composite_local_search(permutation.clone(), &adj_matrix, Improvement::Best);
+            assert_eq!(w, cycle_weight(&p, &adj_matrix));
+            for mv in two_opt_moves(6, false).chain(segment_moves(6, 1, 6)) {
+                let mut moved = p.clone();
+                mv.apply(&mut moved);
+                assert_eq!(mv.weight(&p, &adj_matrix, w, false), cycle_weight(&moved, &adj_matrix), "{mv:?}");
+            }
+        }
+    }
+    #[test]
+    fn candidate_local_search_test() {
+        let instance = Instance::from_file("test_data/1.tsp").unwrap();
+        let adj_matrix = instance_to_matrix(&instance);
+        let candidates = candidate_lists(&instance, CANDIDATES);
+        let length = instance.dimension;
+        let mut rng = Pcg64::seed_from_u64(1);
+        let mut permutation: Vec<usize> = (0..length).collect();
+        permutation.shuffle(&mut rng);
+        for improvement in [Improvement::First, Improvement::Best] {
+            let (p, _counter, w) = candidate_local_search(permutation.clone(), &adj_matrix, &candidates, improvement);
+            assert_eq!(w, permutation_weight(&p, &adj_matrix));
+            let mut pos = vec![0; length];
+            for (i, &city) in p.iter().enumerate() {
+                pos[city] = i;
+            }
+            let moves = (0..length).flat_map(|i| candidate_moves(&p, &pos, &candidates, i));
+            assert!(pick_move(moves, &p, &adj_matrix, w, true, Improvement::First).is_none());
+        }
+    }
+
+    #[test]
+    fn atsp_candidate_moves_test() {
+        let adj_matrix = atsp_matrix();
+        let candidates = nearest_neighbours(&adj_matrix, 5);
+        let mut permutation: Vec<usize> = (0..6).collect();
+        let mut rng = Pcg64::seed_from_u64(1);
+        for _ in 0..20 {
+            permutation.shuffle(&mut rng);
+            let weight = cycle_weight(&permutation, &adj_matrix);
+            let mut pos = vec![0; 6];
+            for (i, &city) in permutation.iter().enumerate() {
+                pos[city] = i;
+            }
+            for i in 0..6 {
+                for mv in candidate_moves(&permutation, &pos, &candidates, i) {
+                    let mut moved = permutation.clone();
+                    mv.apply(&mut moved);
+                    assert_eq!(mv.weight(&permutation, &adj_matrix, weight, false), cycle_weight(&moved, &adj_matrix), "{mv:?}");
+                }
+            }
+            let (p, _counter, w) = candidate_local_search(permutation.clone(), &adj_matrix, &candidates, Improvement::First);
+            assert_eq!(w, cycle_weight(&p, &adj_matrix));
+        }
+    }
+    #[test]
+    fn dlb_local_search_test() {
+        let instance = Instance::from_file("test_data/1.tsp").unwrap();
+        let adj_matrix = instance_to_matrix(&instance);
+        let mut rng = Pcg64::seed_from_u64(1);
+        let mut permutation: Vec<usize> = (0..instance.dimension).collect();
+        permutation.shuffle(&mut rng);
+        let candidates = candidate_lists(&instance, CANDIDATES);
+        let (p, _counter, w) = dlb_local_search(permutation, &adj_matrix, &candidates);
+        assert_eq!(w, permutation_weight(&p, &adj_matrix));
+        let mut pos = vec![0; p.len()];
+        for (i, &city) in p.iter().enumerate() {
+            pos[city] = i;
+        }
+        let reversals = (0..p.len())
+            .flat_map(|i| candidate_moves(&p, &pos, &candidates, i))
+            .filter(|mv| matches!(mv, Move::Reversal(..)));
+        assert!(pick_move(reversals, &p, &adj_matrix, w, true, Improvement::First).is_none());
+        // xqf131 has an optimal tour of 564.
+        assert!(w < 564 * 115 / 100, "{w}");
+
+        // Every other city as a candidate gives the whole 2-opt neighbourhood.
+        let adj_matrix = atsp_matrix();
+        let candidates = nearest_neighbours(&adj_matrix, 5);
+        let mut permutation: Vec<usize> = (0..6).collect();
+        for _ in 0..20 {
+            permutation.shuffle(&mut rng);
+            let (p, _counter, w) = dlb_local_search(permutation.clone(), &adj_matrix, &candidates);
+            assert_eq!(w, cycle_weight(&p, &adj_matrix));
+            assert!(get_neighborhood(&p, &adj_matrix, w, false).iter().all(|a| a.2 >= w));
+        }
+    }
+    #[test]
+    fn linked_local_search_test() {
+        let instance = Instance::from_file("test_data/2.tsp").unwrap();
+        let adj_matrix = instance_to_matrix(&instance);
+        let candidates = candidate_lists(&instance, CANDIDATES);
+        let mst = parent_to_adj_list(&prim(&adj_matrix, instance.dimension));
+        let permutation = dfs_from_point(&mst, 0);
+        let (p, counter, w) = linked_local_search(permutation.clone(), &adj_matrix, &candidates);
+        assert_eq!(w, permutation_weight(&p, &adj_matrix));
+        // xqg237 has an optimal tour of 1019.
+        assert!(w < 1019 * 115 / 100, "{w}");
+        let (list, list_counter) = two_opt_on(TwoLevelList::with_group(&permutation, 5), &adj_matrix, &candidates);
+        assert_eq!((permutation_weight(&list, &adj_matrix), list_counter), (w, counter));
+    }
+    #[test]
+    fn chained_lk_test() {
+        // xit1083 has an optimal tour of 3558.
+        let instance = Instance::from_file("test_data/b.tsp").unwrap();
+        let adj_matrix = instance_to_matrix(&instance);
+        let candidates = candidate_lists(&instance, CANDIDATES);
+        let permutation = dfs_from_point(&parent_to_adj_list(&candidate_prim(&adj_matrix, &candidates)), 0);
+        let lk = LinKernighan::with_candidates(&adj_matrix, candidates);
+        let (_, _, alone) = lk.improve(permutation.clone(), &adj_matrix);
+        let mut rng = Pcg64::seed_from_u64(1);
+        let descend = |p| lk.improve(p, &adj_matrix);
+        let (p, _counter, w) = iterated_local_search(permutation, descend, 100, IlsAcceptance::Better, &mut rng);
+        assert_eq!(w, permutation_weight(&p, &adj_matrix));
+        assert!(w < alone);
+        assert!(w <= 3558 * 102 / 100, "{w}");
+    }
+    #[test]
+    fn distance_backends_test() {
+        let instance = Instance::from_file("test_data/1.tsp").unwrap();
+        let adj_matrix = instance_to_matrix(&instance);
+        let mut permutation: Vec<usize> = (0..instance.dimension).collect();
+        permutation.shuffle(&mut Pcg64::seed_from_u64(1));
+        let expected = local_search(permutation.clone(), &adj_matrix);
+        let packed = tsp_core::PackedMatrix::from_instance(&instance).unwrap();
+        assert_eq!(local_search(permutation.clone(), &packed), expected);
+        let coordinates = tsp_core::CoordinateDistance::new(&instance);
+        assert_eq!(local_search(permutation, &coordinates), expected);
+    }
 }
@@ -0,0 +1,254 @@
+use std::f64::consts::E;
+
+use rand::{Rng, RngCore};
+
+use crate::cooling::CoolingSchedule;
+
+/// Accept/reject rule for a proposed move, shared by every search built on
+/// `accepting_search`.
+pub trait AcceptanceCriterion {
+    /// Whether to go from a tour of weight `current` to one of weight
+    /// `candidate`. Called once per proposal.
+    fn accept(&mut self, current: usize, candidate: usize, rng: &mut dyn RngCore) -> bool;
+    /// Called after every epoch with the share of accepted proposals;
+    /// returning `false` ends the search.
+    fn next_epoch(&mut self, acceptance_rate: f64) -> bool;
+}
+
+impl<C: AcceptanceCriterion + ?Sized> AcceptanceCriterion for Box<C> {
+    fn accept(&mut self, current: usize, candidate: usize, rng: &mut dyn RngCore) -> bool {
+        (**self).accept(current, candidate, rng)
+    }
+
+    fn next_epoch(&mut self, acceptance_rate: f64) -> bool {
+        (**self).next_epoch(acceptance_rate)
+    }
+}
+
+/// Metropolis test at a fixed temperature.
+#[derive(Clone, Debug)]
+pub struct Metropolis {
+    pub temperature: f64,
+}
+
+impl Metropolis {
+    pub fn new(temperature: f64) -> Self {
+        Metropolis { temperature }
+    }
+}
+
+impl AcceptanceCriterion for Metropolis {
+    fn accept(&mut self, current: usize, candidate: usize, rng: &mut dyn RngCore) -> bool {
+        // At zero temperature only the first test decides: the exponent
+        // would be -inf, or NaN for an equal-weight candidate.
+        candidate <= current
+            || (self.temperature > 0.0 && rng.gen_bool(E.powf((current as f64 - candidate as f64) / self.temperature)))
+    }
+
+    fn next_epoch(&mut self, _acceptance_rate: f64) -> bool {
+        true
+    }
+}
+
+/// Metropolis test whose temperature follows `schedule` down to
+/// `final_temperature`: simulated annealing.
+pub struct Annealing<S> {
+    pub metropolis: Metropolis,
+    pub final_temperature: f64,
+    pub schedule: S,
+}
+
+impl<S: CoolingSchedule> Annealing<S> {
+    pub fn new(temperature: f64, final_temperature: f64, schedule: S) -> Self {
+        Annealing {
+            metropolis: Metropolis::new(temperature),
+            final_temperature,
+            schedule,
+        }
+    }
+}
+
+impl<S: CoolingSchedule> AcceptanceCriterion for Annealing<S> {
+    fn accept(&mut self, current: usize, candidate: usize, rng: &mut dyn RngCore) -> bool {
+        self.metropolis.accept(current, candidate, rng)
+    }
+
+    fn next_epoch(&mut self, acceptance_rate: f64) -> bool {
+        let temperature = &mut self.metropolis.temperature;
+        *temperature = self.schedule.next(*temperature, acceptance_rate);
+        *temperature > self.final_temperature
+    }
+}
+
+/// Accepts anything less than `threshold` worse than the current tour. The
+/// threshold shrinks by `alpha` per epoch; the search ends once it drops below
+/// one, where no worsening move passes any more.
+#[derive(Clone, Debug)]
+pub struct ThresholdAccepting {
+    pub threshold: f64,
+    pub alpha: f64,
+}
+
+impl ThresholdAccepting {
+    pub fn new(threshold: f64, alpha: f64) -> Self {
+        ThresholdAccepting { threshold, alpha }
+    }
+}
+
+impl AcceptanceCriterion for ThresholdAccepting {
+    fn accept(&mut self, current: usize, candidate: usize, _rng: &mut dyn RngCore) -> bool {
+        (candidate as f64) < current as f64 + self.threshold
+    }
+
+    fn next_epoch(&mut self, _acceptance_rate: f64) -> bool {
+        self.threshold *= self.alpha;
+        self.threshold >= 1.0
+    }
+}
+
+/// Great deluge: accepts any tour not heavier than the water `level`, which
+/// sinks by `rain` on every proposal. Ends when a whole epoch is rejected.
+#[derive(Clone, Debug)]
+pub struct GreatDeluge {
+    pub level: f64,
+    pub rain: f64,
+}
+
+impl GreatDeluge {
+    pub fn new(level: f64, rain: f64) -> Self {
+        GreatDeluge { level, rain }
+    }
+}
+
+impl AcceptanceCriterion for GreatDeluge {
+    fn accept(&mut self, current: usize, candidate: usize, _rng: &mut dyn RngCore) -> bool {
+        self.level -= self.rain;
+        candidate < current || candidate as f64 <= self.level
+    }
+
+    fn next_epoch(&mut self, acceptance_rate: f64) -> bool {
+        acceptance_rate > 0.0
+    }
+}
+
+/// Record-to-record travel: accepts any tour within `deviation` (a fraction)
+/// of the best weight seen. Ends when a whole epoch is rejected.
+#[derive(Clone, Debug)]
+pub struct RecordToRecord {
+    pub deviation: f64,
+    record: usize,
+}
+
+impl RecordToRecord {
+    pub fn new(deviation: f64) -> Self {
+        RecordToRecord {
+            deviation,
+            record: usize::MAX,
+        }
+    }
+}
+
+impl AcceptanceCriterion for RecordToRecord {
+    fn accept(&mut self, current: usize, candidate: usize, _rng: &mut dyn RngCore) -> bool {
+        self.record = self.record.min(current);
+        let accepted = candidate < current || (candidate as f64) < self.record as f64 * (1.0 + self.deviation);
+        if accepted {
+            self.record = self.record.min(candidate);
+        }
+        accepted
+    }
+
+    fn next_epoch(&mut self, acceptance_rate: f64) -> bool {
+        acceptance_rate > 0.0
+    }
+}
+
+/// Late-acceptance hill climbing: compares the candidate with the weight the
+/// search had `length` proposals ago. Ends when a whole epoch is rejected.
+#[derive(Clone, Debug)]
+pub struct LateAcceptance {
+    history: Vec<usize>,
+    length: usize,
+    step: usize,
+}
+
+impl LateAcceptance {
+    pub fn new(length: usize) -> Self {
+        LateAcceptance {
+            history: Vec::new(),
+            length: length.max(1),
+            step: 0,
+        }
+    }
+}
+
+impl AcceptanceCriterion for LateAcceptance {
+    fn accept(&mut self, current: usize, candidate: usize, _rng: &mut dyn RngCore) -> bool {
+        if self.history.is_empty() {
+            self.history = vec![current; self.length];
+        }
+        let slot = self.step % self.length;
+        let accepted = candidate <= current || candidate <= self.history[slot];
+        self.history[slot] = if accepted { candidate } else { current };
+        self.step += 1;
+        accepted
+    }
+
+    fn next_epoch(&mut self, acceptance_rate: f64) -> bool {
+        acceptance_rate > 0.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_pcg::Pcg64;
+
+    #[test]
+    fn deterministic_rules() {
+        let mut rng = Pcg64::seed_from_u64(1);
+        let mut threshold = ThresholdAccepting::new(10.0, 0.5);
+        assert!(threshold.accept(100, 109, &mut rng));
+        assert!(!threshold.accept(100, 110, &mut rng));
+        assert!(threshold.next_epoch(0.5));
+        assert!(!threshold.accept(100, 105, &mut rng));
+
+        let mut deluge = GreatDeluge::new(102.0, 1.0);
+        assert!(deluge.accept(100, 101, &mut rng));
+        assert!(!deluge.accept(100, 101, &mut rng));
+        assert!(deluge.accept(100, 99, &mut rng));
+
+        let mut rrt = RecordToRecord::new(0.1);
+        assert!(rrt.accept(100, 90, &mut rng));
+        assert!(rrt.accept(90, 98, &mut rng));
+        assert!(!rrt.accept(98, 100, &mut rng));
+
+        let mut lahc = LateAcceptance::new(2);
+        assert!(lahc.accept(100, 100, &mut rng));
+        assert!(lahc.accept(100, 90, &mut rng));
+        assert!(lahc.accept(90, 95, &mut rng));
+        assert!(!lahc.accept(95, 96, &mut rng));
+    }
+
+    #[test]
+    fn annealing_cools_until_final_temperature() {
+        use crate::cooling::Geometric;
+        let mut annealing = Annealing::new(10.0, 1.0, Geometric::new(0.5));
+        assert!(annealing.next_epoch(1.0));
+        assert!(annealing.next_epoch(1.0));
+        assert!(annealing.next_epoch(1.0));
+        assert!(!annealing.next_epoch(1.0));
+        let mut rng = Pcg64::seed_from_u64(1);
+        assert!(annealing.accept(10, 9, &mut rng));
+    }
+
+    #[test]
+    fn zero_temperature_is_a_descent() {
+        let mut rng = Pcg64::seed_from_u64(1);
+        let mut metropolis = Metropolis::new(0.0);
+        assert!(metropolis.accept(10, 9, &mut rng));
+        assert!(metropolis.accept(10, 10, &mut rng));
+        assert!(!metropolis.accept(10, 11, &mut rng));
+    }
+}
@@ -0,0 +1,346 @@
+use rand::Rng;
+use rand::SeedableRng;
+use rand_pcg::Pcg64;
+
+use crate::acceptance::{AcceptanceCriterion, Annealing};
+use crate::cooling::CoolingSchedule;
+use tsp_core::moves::Move;
+use tsp_core::{is_symmetric, permutation_weight, Distance};
+
+/// Move operator proposed at every step of `simulated_annealing_with`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Neighborhood {
+    #[default]
+    Swap,
+    /// A single city moved elsewhere in the tour.
+    Insertion,
+    TwoOpt,
+    /// A segment of up to `OR_OPT_SEGMENT` cities moved elsewhere.
+    OrOpt,
+}
+
+const OR_OPT_SEGMENT: usize = 3;
+
+/// Uniformly draws a move of the given kind on a tour of `length >= 4` cities.
+pub fn random_move(neighborhood: Neighborhood, length: usize, rng: &mut impl Rng) -> Move {
+    match neighborhood {
+        Neighborhood::Swap => {
+            let (i, j) = random_pair(length, rng);
+            Move::Swap(i, j)
+        }
+        Neighborhood::TwoOpt => loop {
+            let (i, j) = random_pair(length, rng);
+            // Reversing the whole tour gives the same cycle.
+            if i != 0 || j != length - 1 {
+                return Move::Reversal(i, j);
+            }
+        },
+        Neighborhood::Insertion => random_insertion(1, length, rng),
+        Neighborhood::OrOpt => {
+            let segment = rng.gen_range(1..=OR_OPT_SEGMENT.min(length - 2));
+            random_insertion(segment, length, rng)
+        }
+    }
+}
+
+fn random_pair(length: usize, rng: &mut impl Rng) -> (usize, usize) {
+    let i = rng.gen_range(0..length);
+    let j = (i + rng.gen_range(1..length)) % length;
+    (i.min(j), i.max(j))
+}
+
+fn random_insertion(segment: usize, length: usize, rng: &mut impl Rng) -> Move {
+    let i = rng.gen_range(0..=length - segment);
+    let j = i + segment - 1;
+    loop {
+        let k = rng.gen_range(0..length);
+        if (k < i || k > j) && k != (i + length - 1) % length {
+            return Move::Insertion { i, j, k };
+        }
+    }
+}
+
+/// Starting point and stopping rules for `simulated_annealing_with`.
+#[derive(Clone, Debug)]
+pub struct AnnealingParams {
+    /// Calibrated to `INITIAL_ACCEPTANCE` when `None`.
+    pub temperature: Option<f64>,
+    /// The search stops once the schedule cools below this. Calibrated to
+    /// `FINAL_ACCEPTANCE` when `None`.
+    pub final_temperature: Option<f64>,
+    /// Proposals per temperature.
+    pub epoch_count: usize,
+    /// Cap on the number of temperatures, for schedules that cool slowly.
+    pub max_epochs: usize,
+    pub neighborhood: Neighborhood,
+}
+
+impl AnnealingParams {
+    pub fn new(temperature: f64, epoch_count: usize) -> Self {
+        AnnealingParams {
+            temperature: Some(temperature),
+            final_temperature: Some(0.01),
+            epoch_count,
+            max_epochs: 10_000,
+            neighborhood: Neighborhood::default(),
+        }
+    }
+
+    pub fn calibrated(epoch_count: usize) -> Self {
+        AnnealingParams {
+            temperature: None,
+            final_temperature: None,
+            ..AnnealingParams::new(0.0, epoch_count)
+        }
+    }
+}
+
+/// Share of worsening moves accepted at the calibrated start and end
+/// temperatures (Johnson et al. start at 80%).
+pub const INITIAL_ACCEPTANCE: f64 = 0.8;
+pub const FINAL_ACCEPTANCE: f64 = 0.001;
+const CALIBRATION_SAMPLES: usize = 1000;
+
+/// Temperature at which, on average, `acceptance` of the worsening moves
+/// sampled around `solution` would pass the Metropolis test. Returns 1.0 when
+/// no sampled move makes the tour worse.
+pub fn calibrate_temperature<D: Distance + ?Sized>(
+    adj_matrix: &D,
+    solution: &[usize],
+    neighborhood: Neighborhood,
+    acceptance: f64,
+    rng: &mut impl Rng,
+) -> f64 {
+    let length = solution.len();
+    if length < 4 {
+        return 1.0;
+    }
+    let symmetric = is_symmetric(adj_matrix);
+    let weight = permutation_weight(solution, adj_matrix);
+    let worsening: Vec<f64> = (0..CALIBRATION_SAMPLES)
+        .map(|_| random_move(neighborhood, length, rng).weight(solution, adj_matrix, weight, symmetric))
+        .filter(|&w| w > weight)
+        .map(|w| (w - weight) as f64)
+        .collect();
+    if worsening.is_empty() {
+        return 1.0;
+    }
+    let accepted = |temperature: f64| worsening.iter().map(|d| (-d / temperature).exp()).sum::<f64>() / worsening.len() as f64;
+    let mut low = 0.0;
+    let mut high = worsening.iter().cloned().fold(1.0, f64::max);
+    while accepted(high) < acceptance {
+        low = high;
+        high *= 2.0;
+    }
+    for _ in 0..64 {
+        let mid = (low + high) / 2.0;
+        if accepted(mid) < acceptance {
+            low = mid;
+        } else {
+            high = mid;
+        }
+    }
+    high
+}
+
+/// Simulated annealing driven by `schedule`.
+pub fn simulated_annealing_with<D: Distance + ?Sized>(
+    adj_matrix: &D,
+    solution: Vec<usize>,
+    params: &AnnealingParams,
+    schedule: &mut dyn CoolingSchedule,
+) -> (Vec<usize>, usize) {
+    let mut rng = Pcg64::from_entropy();
+    let temperature = params
+        .temperature
+        .unwrap_or_else(|| calibrate_temperature(adj_matrix, &solution, params.neighborhood, INITIAL_ACCEPTANCE, &mut rng));
+    let final_temperature = params
+        .final_temperature
+        .unwrap_or_else(|| calibrate_temperature(adj_matrix, &solution, params.neighborhood, FINAL_ACCEPTANCE, &mut rng));
+    accepting_search(adj_matrix, solution, params, &mut Annealing::new(temperature, final_temperature, schedule))
+}
+
+/// Random-move search where `criterion` decides which proposals to take. Each
+/// proposal is priced from the edges it touches and applied only when
+/// accepted. Only `epoch_count`, `max_epochs` and `neighborhood` are read from
+/// `params`. Returns the best tour seen.
+pub fn accepting_search<D: Distance + ?Sized>(
+    adj_matrix: &D,
+    solution: Vec<usize>,
+    params: &AnnealingParams,
+    criterion: &mut dyn AcceptanceCriterion,
+) -> (Vec<usize>, usize) {
+    let mut state = SearchState::new(solution, adj_matrix);
+    if state.solution.len() < 4 || params.epoch_count == 0 {
+        return (state.best, state.best_weight);
+    }
+    let symmetric = is_symmetric(adj_matrix);
+    let mut rng = Pcg64::from_entropy();
+    for _ in 0..params.max_epochs {
+        let accepted = run_epoch(adj_matrix, &mut state, criterion, params, symmetric, &mut rng);
+        if !criterion.next_epoch(accepted as f64 / params.epoch_count as f64) {
+            break;
+        }
+    }
+    (state.best, state.best_weight)
+}
+
+pub(crate) struct SearchState {
+    pub solution: Vec<usize>,
+    pub weight: usize,
+    pub best: Vec<usize>,
+    pub best_weight: usize,
+}
+
+impl SearchState {
+    pub fn new<D: Distance + ?Sized>(solution: Vec<usize>, adj_matrix: &D) -> Self {
+        let weight = permutation_weight(&solution, adj_matrix);
+        SearchState {
+            best: solution.clone(),
+            solution,
+            weight,
+            best_weight: weight,
+        }
+    }
+}
+
+/// `params.epoch_count` proposals; returns how many were accepted.
+pub(crate) fn run_epoch<D: Distance + ?Sized>(
+    adj_matrix: &D,
+    state: &mut SearchState,
+    criterion: &mut dyn AcceptanceCriterion,
+    params: &AnnealingParams,
+    symmetric: bool,
+    rng: &mut Pcg64,
+) -> usize {
+    let mut accepted = 0;
+    for _epoch in 0..params.epoch_count {
+        let proposal = random_move(params.neighborhood, state.solution.len(), rng);
+        let potential_weight = proposal.weight(&state.solution, adj_matrix, state.weight, symmetric);
+        if criterion.accept(state.weight, potential_weight, rng) {
+            proposal.apply(&mut state.solution);
+            state.weight = potential_weight;
+            accepted += 1;
+            if state.weight < state.best_weight {
+                state.best.clone_from(&state.solution);
+                state.best_weight = state.weight;
+            }
+        }
+    }
+    accepted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cooling::{Adaptive, Geometric, Linear, Logarithmic, LundyMees, Reheating};
+    use tsp_core::{instance_to_matrix, Instance};
+
+    #[test]
+    fn every_neighborhood_tracks_the_true_weight() {
+        let instance = Instance::from_file("test_data/1.tsp").unwrap();
+        let adj_matrix = instance_to_matrix(&instance);
+        let start: Vec<usize> = (0..instance.dimension).collect();
+        for neighborhood in [Neighborhood::Swap, Neighborhood::Insertion, Neighborhood::TwoOpt, Neighborhood::OrOpt] {
+            let params = AnnealingParams { neighborhood, ..AnnealingParams::new(50.0, 2000) };
+            let (tour, weight) = simulated_annealing_with(&adj_matrix, start.clone(), &params, &mut Geometric::new(0.94));
+            assert_eq!(weight, permutation_weight(&tour, &adj_matrix), "{neighborhood:?}");
+            let mut sorted = tour.clone();
+            sorted.sort();
+            assert_eq!(sorted, start);
+        }
+    }
+
+    #[test]
+    fn random_moves_are_valid() {
+        let mut rng = Pcg64::seed_from_u64(7);
+        for length in 4..12 {
+            for _ in 0..200 {
+                match random_move(Neighborhood::OrOpt, length, &mut rng) {
+                    Move::Insertion { i, j, k } => {
+                        assert!(i <= j && j < length && j - i < OR_OPT_SEGMENT);
+                        assert!((k < i || k > j) && k != (i + length - 1) % length);
+                    }
+                    other => panic!("unexpected {other:?}"),
+                }
+                match random_move(Neighborhood::TwoOpt, length, &mut rng) {
+                    Move::Reversal(i, j) => assert!(i < j && j < length && (i, j) != (0, length - 1)),
+                    other => panic!("unexpected {other:?}"),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn every_schedule_finishes() {
+        let instance = Instance::from_file("test_data/1.tsp").unwrap();
+        let adj_matrix = instance_to_matrix(&instance);
+        let start: Vec<usize> = (0..instance.dimension).collect();
+        let params = AnnealingParams { max_epochs: 200, ..AnnealingParams::new(100.0, 500) };
+        let schedules: Vec<Box<dyn CoolingSchedule>> = vec![
+            Box::new(Geometric::new(0.94)),
+            Box::new(Linear::new(1.0)),
+            Box::new(Logarithmic::new(100.0)),
+            Box::new(LundyMees::new(0.001)),
+            Box::new(Adaptive::new(0.3)),
+            Box::new(Reheating::new(Geometric::new(0.9), 0.01, 3.0, 5)),
+        ];
+        for mut schedule in schedules {
+            let (tour, weight) = simulated_annealing_with(&adj_matrix, start.clone(), &params, &mut schedule);
+            assert_eq!(weight, permutation_weight(&tour, &adj_matrix));
+        }
+    }
+
+    #[test]
+    fn calibration_hits_the_target_acceptance() {
+        let instance = Instance::from_file("test_data/1.tsp").unwrap();
+        let adj_matrix = instance_to_matrix(&instance);
+        let solution: Vec<usize> = (0..instance.dimension).collect();
+        let mut rng = Pcg64::seed_from_u64(3);
+        let initial = calibrate_temperature(&adj_matrix, &solution, Neighborhood::TwoOpt, INITIAL_ACCEPTANCE, &mut rng);
+        let last = calibrate_temperature(&adj_matrix, &solution, Neighborhood::TwoOpt, FINAL_ACCEPTANCE, &mut rng);
+        assert!(last < initial);
+        // Check the acceptance on a fresh sample of moves.
+        let symmetric = is_symmetric(&adj_matrix);
+        let weight = permutation_weight(&solution, &adj_matrix);
+        let deltas: Vec<f64> = (0..5000)
+            .map(|_| random_move(Neighborhood::TwoOpt, solution.len(), &mut rng).weight(&solution, &adj_matrix, weight, symmetric))
+            .filter(|&w| w > weight)
+            .map(|w| (w - weight) as f64)
+            .collect();
+        let rate = deltas.iter().map(|d| (-d / initial).exp()).sum::<f64>() / deltas.len() as f64;
+        assert!((rate - INITIAL_ACCEPTANCE).abs() < 0.05, "{rate}");
+    }
+
+    #[test]
+    fn calibrated_run_finishes() {
+        let instance = Instance::from_file("test_data/1.tsp").unwrap();
+        let adj_matrix = instance_to_matrix(&instance);
+        let start: Vec<usize> = (0..instance.dimension).collect();
+        let params = AnnealingParams { neighborhood: Neighborhood::TwoOpt, ..AnnealingParams::calibrated(1000) };
+        let (tour, weight) = simulated_annealing_with(&adj_matrix, start.clone(), &params, &mut Geometric::new(0.9));
+        assert_eq!(weight, permutation_weight(&tour, &adj_matrix));
+        assert!(weight < permutation_weight(&start, &adj_matrix));
+    }
+
+    #[test]
+    fn every_acceptance_criterion_improves() {
+        use crate::acceptance::{GreatDeluge, LateAcceptance, RecordToRecord, ThresholdAccepting};
+        let instance = Instance::from_file("test_data/1.tsp").unwrap();
+        let adj_matrix = instance_to_matrix(&instance);
+        let start: Vec<usize> = (0..instance.dimension).map(|k| (k * 37) % instance.dimension).collect();
+        let start_weight = permutation_weight(&start, &adj_matrix);
+        let params = AnnealingParams { neighborhood: Neighborhood::TwoOpt, max_epochs: 300, ..AnnealingParams::calibrated(1000) };
+        let criteria: Vec<Box<dyn AcceptanceCriterion>> = vec![
+            Box::new(ThresholdAccepting::new(50.0, 0.95)),
+            Box::new(GreatDeluge::new(start_weight as f64, start_weight as f64 / 300_000.0)),
+            Box::new(RecordToRecord::new(0.02)),
+            Box::new(LateAcceptance::new(500)),
+        ];
+        for mut criterion in criteria {
+            let (tour, weight) = accepting_search(&adj_matrix, start.clone(), &params, &mut criterion);
+            assert_eq!(weight, permutation_weight(&tour, &adj_matrix));
+            assert!(weight < start_weight);
+        }
+    }
+}
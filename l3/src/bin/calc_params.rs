@@ -2,8 +2,24 @@ use std::time::Instant;
 use std::sync::{Arc, Mutex};
 use std::thread;
 
+use rand::seq::SliceRandom;
+
 use l3::*;
 
+const COOLING_SCHEDULES: [&str; 6] = ["geometric", "linear", "logarithmic", "lundy-mees", "adaptive", "reheating"];
+
+fn cooling_schedule(name: &str, temperature: f64) -> Box<dyn CoolingSchedule> {
+    match name {
+        "geometric" => Box::new(Geometric::new(0.94)),
+        "linear" => Box::new(Linear::new(temperature / 100.0)),
+        "logarithmic" => Box::new(Logarithmic::new(temperature)),
+        "lundy-mees" => Box::new(LundyMees::new(0.05 / temperature)),
+        "adaptive" => Box::new(Adaptive::new(0.3)),
+        "reheating" => Box::new(Reheating::new(Geometric::new(0.94), 0.01, 4.0, 3)),
+        _ => unreachable!(),
+    }
+}
+
 fn main() {
     for path in [
         "test_data/1.tsp",
@@ -18,9 +34,15 @@ fn main() {
         "test_data/a.tsp",
     ] {
         let mut results_sa: Vec<(usize, usize, usize, usize, f64)> = Vec::new();
-        let points = file_to_points(path);
-        let point_count = points.len();
-        let adj_matrix = points_to_matrix(points);
+        let instance = match Instance::from_file(path) {
+            Ok(instance) => instance,
+            Err(err) => {
+                eprintln!("{path}: {err}");
+                continue;
+            }
+        };
+        let point_count = instance.dimension;
+        let adj_matrix = instance_to_matrix(&instance);
         for temp in 1..=4 {
             for epoch_count in (500..=5_000).step_by(500){
                 let mut handles = Vec::new();
@@ -34,8 +56,8 @@ fn main() {
                         let mut time = time_acc.lock().unwrap();
                         let mut weight = weight_acc.lock().unwrap();
                         let before = Instant::now();
-                        let temperature = ((point_count * temp) as f64 * 0.5) as usize;
-                        let sa = simulated_annealing(&adj_matrix, temperature, epoch_count);
+                        let temperature = (point_count * temp) as f64 * 0.5;
+                        let sa = simulated_annealing(&adj_matrix, Some(temperature), epoch_count);
                         *time += before.elapsed().as_secs_f64();
                         *weight += sa.1;
                     });
@@ -47,7 +69,43 @@ fn main() {
                 results_sa.push((point_count, temp, epoch_count, *weight_acc.clone().lock().unwrap()/10, *time_acc.clone().lock().unwrap()/10.0));
             }
         }
-        println!("{:?}", results_sa.iter().min_by_key(|x| x.3).unwrap());
+        let best_sa = *results_sa.iter().min_by_key(|x| x.3).unwrap();
+        println!("{:?}", best_sa);
+        let (_, temp, epoch_count, _, _) = best_sa;
+        let mut start: Vec<usize> = (0..point_count).collect();
+        start.shuffle(&mut rand::thread_rng());
+        let calibrated = calibrate_temperature(&adj_matrix, &start, Neighborhood::default(), INITIAL_ACCEPTANCE, &mut rand::thread_rng());
+        let calibrated_weight = (0..10).map(|_| simulated_annealing(&adj_matrix, None, epoch_count).1).sum::<usize>() / 10;
+        println!("{:?}", (point_count, calibrated, epoch_count, calibrated_weight));
+        let temperature = (point_count * temp) as f64 * 0.5;
+        let mut results_cooling: Vec<(usize, &str, usize, f64)> = Vec::new();
+        for name in COOLING_SCHEDULES {
+            let mut handles = Vec::new();
+            let time_acc: Arc<Mutex<f64>> = Arc::new(Mutex::new(0.0));
+            let weight_acc: Arc<Mutex<usize>> = Arc::new(Mutex::new(0));
+            for _ in 0..10 {
+                let time_acc = Arc::clone(&time_acc);
+                let weight_acc = Arc::clone(&weight_acc);
+                let adj_matrix = adj_matrix.clone();
+                let handle = thread::spawn(move || {
+                    let mut time = time_acc.lock().unwrap();
+                    let mut weight = weight_acc.lock().unwrap();
+                    let before = Instant::now();
+                    let mut start: Vec<usize> = (0..point_count).collect();
+                    start.shuffle(&mut rand::thread_rng());
+                    let params = AnnealingParams::new(temperature, epoch_count);
+                    let sa = simulated_annealing_with(&adj_matrix, start, &params, &mut cooling_schedule(name, temperature));
+                    *time += before.elapsed().as_secs_f64();
+                    *weight += sa.1;
+                });
+                handles.push(handle);
+            }
+            for handle in handles {
+                handle.join().unwrap();
+            }
+            results_cooling.push((point_count, name, *weight_acc.clone().lock().unwrap()/10, *time_acc.clone().lock().unwrap()/10.0));
+        }
+        println!("{:?}", results_cooling.iter().min_by_key(|x| x.2).unwrap());
         let mut results_ts: Vec<(usize, usize, usize, f64)> = Vec::new();
         for tabu in 1..=32 {
             let mut handles = Vec::new();
@@ -61,8 +119,8 @@ fn main() {
                     let mut time = time_acc.lock().unwrap();
                     let mut weight = weight_acc.lock().unwrap();
                     let before = Instant::now();
-                    let tabu_capacity = ((point_count * tabu) as f64 * 0.0625) as usize;
-                    let sa = tabu_search(&adj_matrix, tabu_capacity);
+                    let tenure = ((point_count * tabu) as f64 * 0.0625) as usize;
+                    let sa = tabu_search(&adj_matrix, tenure);
                     *time += before.elapsed().as_secs_f64();
                     *weight += sa.1;
                 });
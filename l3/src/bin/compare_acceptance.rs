@@ -0,0 +1,57 @@
+use rand::seq::SliceRandom;
+
+use l3::*;
+
+const NAMES: [&str; 5] = ["annealing", "threshold", "deluge", "record", "late"];
+
+fn criterion(name: &str, temperature: f64, weight: usize, proposals: usize) -> Box<dyn AcceptanceCriterion> {
+    match name {
+        "annealing" => Box::new(Annealing::new(temperature, temperature / 1000.0, Geometric::new(0.99))),
+        "threshold" => Box::new(ThresholdAccepting::new(temperature, 0.99)),
+        "deluge" => Box::new(GreatDeluge::new(weight as f64, weight as f64 * 0.9 / proposals as f64)),
+        "record" => Box::new(RecordToRecord::new(0.01)),
+        "late" => Box::new(LateAcceptance::new(100)),
+        _ => unreachable!(),
+    }
+}
+
+fn main() {
+    for path in [
+        "test_data/1.tsp",
+        "test_data/2.tsp",
+        "test_data/3.tsp",
+        "test_data/4.tsp",
+        "test_data/5.tsp",
+        "test_data/6.tsp",
+        "test_data/7.tsp",
+        "test_data/8.tsp",
+        "test_data/9.tsp",
+        "test_data/a.tsp",
+    ] {
+        let instance = match Instance::from_file(path) {
+            Ok(instance) => instance,
+            Err(err) => {
+                eprintln!("{path}: {err}");
+                continue;
+            }
+        };
+        let point_count = instance.dimension;
+        let adj_matrix = instance_to_matrix(&instance);
+        let params = AnnealingParams { neighborhood: Neighborhood::TwoOpt, max_epochs: 1000, ..AnnealingParams::calibrated(1000) };
+        for name in NAMES {
+            let mut best = usize::MAX;
+            let mut avg = 0.;
+            for _ in 0..10 {
+                let mut start: Vec<usize> = (0..point_count).collect();
+                start.shuffle(&mut rand::thread_rng());
+                let temperature = calibrate_temperature(&adj_matrix, &start, params.neighborhood, INITIAL_ACCEPTANCE, &mut rand::thread_rng());
+                let weight = permutation_weight(&start, &adj_matrix);
+                let mut criterion = criterion(name, temperature, weight, params.epoch_count * params.max_epochs);
+                let (_, w) = accepting_search(&adj_matrix, start, &params, &mut criterion);
+                avg += w as f64 / 10.0;
+                best = best.min(w);
+            }
+            println!("{};{};{};{}", instance.name, name, best, avg);
+        }
+    }
+}
@@ -1,6 +1,13 @@
+use std::fs;
+use std::time::Duration;
+
+use rand::seq::SliceRandom;
+
 use l3::*;
+use tsp_core::with_flat_matrix;
 
 fn main() {
+    fs::create_dir_all("tours").unwrap();
     for path in [
         "test_data/b.tsp",
         "test_data/c.tsp",
@@ -8,30 +15,59 @@ fn main() {
         "test_data/e.tsp",
         "test_data/f.tsp",
     ] {
-        let points = file_to_points(path);
-        let point_count = points.len();
-        let adj_matrix = points_to_matrix(points);
-        let mut best_sa = usize::MAX;
-        let mut best_ts = usize::MAX;
-        let mut avg_sa = 0.;
-        let mut avg_ts = 0.;
-        println!("map: {:?}", point_count);
-        for _ in 0..100 {
-            let sa = simulated_annealing(&adj_matrix, point_count/2, 5000).1;
-            avg_sa += sa as f64 / 100.0;
-            if sa < best_sa {
-                best_sa = sa;
+        let instance = match Instance::from_file(path) {
+            Ok(instance) => instance,
+            Err(err) => {
+                eprintln!("{path}: {err}");
+                continue;
             }
+        };
+        let adj_matrix = FlatMatrix::from_instance(&instance);
+        with_flat_matrix!(&adj_matrix, matrix => run(&instance, matrix));
+    }
+}
 
-            let ts = tabu_search(&adj_matrix,  point_count/2).1;
-            avg_ts += ts as f64 / 100.0;
-            if ts < best_ts {
-                best_ts = ts;
-            }
+// SA, tabu search and parallel tempering on one instance, with the
+// matrix at its own width so the searches are compiled for it.
+fn run<D: Distance + Sync>(instance: &Instance, adj_matrix: &D) {
+    let point_count = instance.dimension;
+    let mut best_sa = usize::MAX;
+    let mut best_ts = usize::MAX;
+    let mut best_sa_tour = Vec::new();
+    let mut best_ts_tour = Vec::new();
+    let mut avg_sa = 0.;
+    let mut avg_ts = 0.;
+    let tabu_params = TabuParams { time_limit: Some(Duration::from_secs(10)), ..TabuParams::new(point_count / 2) };
+    println!("map: {:?}", point_count);
+    for _ in 0..100 {
+        let (sa_tour, sa) = simulated_annealing(adj_matrix, None, 5000);
+        avg_sa += sa as f64 / 100.0;
+        if sa < best_sa {
+            best_sa = sa;
+            best_sa_tour = sa_tour;
+        }
+
+        let mut start: Vec<usize> = (0..point_count).collect();
+        start.shuffle(&mut rand::thread_rng());
+        let (ts_tour, ts) = tabu_search_with(adj_matrix, start, &tabu_params);
+        avg_ts += ts as f64 / 100.0;
+        if ts < best_ts {
+            best_ts = ts;
+            best_ts_tour = ts_tour;
         }
-        println!("best_ts: {:?}", best_ts);
-        println!("avg_ts: {:?}", avg_ts);
-        println!("best_sa: {:?}", best_sa);
-        println!("avg_sa: {:?}", avg_sa);
     }
+    let mut start: Vec<usize> = (0..point_count).collect();
+    start.shuffle(&mut rand::thread_rng());
+    let pt_params = AnnealingParams { max_epochs: 2000, ..AnnealingParams::calibrated(5000) };
+    let (pt_tour, pt) = parallel_tempering(adj_matrix, start, &pt_params, rayon::current_num_threads().max(2));
+    let hk_bound = held_karp_bound(adj_matrix, pt.min(best_ts).min(best_sa), HELD_KARP_ITERATIONS);
+    println!("hk_bound: {:?}", hk_bound);
+    println!("pt: {:?}", pt);
+    println!("best_ts: {:?}", best_ts);
+    println!("avg_ts: {:?}", avg_ts);
+    println!("best_sa: {:?}", best_sa);
+    println!("avg_sa: {:?}", avg_sa);
+    write_tour(format!("tours/{}.sa.tour", instance.name), &instance.name, &format!("Length {best_sa}"), &best_sa_tour).unwrap();
+    write_tour(format!("tours/{}.ts.tour", instance.name), &instance.name, &format!("Length {best_ts}"), &best_ts_tour).unwrap();
+    write_tour(format!("tours/{}.pt.tour", instance.name), &instance.name, &format!("Length {pt}"), &pt_tour).unwrap();
 }
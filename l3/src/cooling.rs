@@ -0,0 +1,206 @@
+use std::f64::consts::E;
+
+/// Decides the temperature of the next epoch of simulated annealing.
+pub trait CoolingSchedule {
+    /// `acceptance_rate` is the share of proposals accepted during the epoch
+    /// that just ended at `temperature`.
+    fn next(&mut self, temperature: f64, acceptance_rate: f64) -> f64;
+}
+
+impl<S: CoolingSchedule + ?Sized> CoolingSchedule for Box<S> {
+    fn next(&mut self, temperature: f64, acceptance_rate: f64) -> f64 {
+        (**self).next(temperature, acceptance_rate)
+    }
+}
+
+impl<S: CoolingSchedule + ?Sized> CoolingSchedule for &mut S {
+    fn next(&mut self, temperature: f64, acceptance_rate: f64) -> f64 {
+        (**self).next(temperature, acceptance_rate)
+    }
+}
+
+/// `T * alpha`.
+#[derive(Clone, Debug)]
+pub struct Geometric {
+    pub alpha: f64,
+}
+
+impl Geometric {
+    pub fn new(alpha: f64) -> Self {
+        Geometric { alpha }
+    }
+}
+
+impl CoolingSchedule for Geometric {
+    fn next(&mut self, temperature: f64, _acceptance_rate: f64) -> f64 {
+        temperature * self.alpha
+    }
+}
+
+/// `T - step`, never below zero.
+#[derive(Clone, Debug)]
+pub struct Linear {
+    pub step: f64,
+}
+
+impl Linear {
+    pub fn new(step: f64) -> Self {
+        Linear { step }
+    }
+}
+
+impl CoolingSchedule for Linear {
+    fn next(&mut self, temperature: f64, _acceptance_rate: f64) -> f64 {
+        (temperature - self.step).max(0.0)
+    }
+}
+
+/// `T0 / ln(e + k)` after `k` epochs. Cools very slowly, so it needs an epoch
+/// cap to finish.
+#[derive(Clone, Debug)]
+pub struct Logarithmic {
+    pub initial: f64,
+    epoch: usize,
+}
+
+impl Logarithmic {
+    pub fn new(initial: f64) -> Self {
+        Logarithmic { initial, epoch: 0 }
+    }
+}
+
+impl CoolingSchedule for Logarithmic {
+    fn next(&mut self, _temperature: f64, _acceptance_rate: f64) -> f64 {
+        self.epoch += 1;
+        self.initial / (E + self.epoch as f64).ln()
+    }
+}
+
+/// Lundy–Mees: `T / (1 + beta * T)`.
+#[derive(Clone, Debug)]
+pub struct LundyMees {
+    pub beta: f64,
+}
+
+impl LundyMees {
+    pub fn new(beta: f64) -> Self {
+        LundyMees { beta }
+    }
+}
+
+impl CoolingSchedule for LundyMees {
+    fn next(&mut self, temperature: f64, _acceptance_rate: f64) -> f64 {
+        temperature / (1.0 + self.beta * temperature)
+    }
+}
+
+/// Cools with `fast` while more than `target` of the proposals are accepted
+/// and with `slow` once the search gets choosy.
+#[derive(Clone, Debug)]
+pub struct Adaptive {
+    pub target: f64,
+    pub fast: f64,
+    pub slow: f64,
+}
+
+impl Adaptive {
+    pub fn new(target: f64) -> Self {
+        Adaptive {
+            target,
+            fast: 0.8,
+            slow: 0.97,
+        }
+    }
+}
+
+impl CoolingSchedule for Adaptive {
+    fn next(&mut self, temperature: f64, acceptance_rate: f64) -> f64 {
+        if acceptance_rate > self.target {
+            temperature * self.fast
+        } else {
+            temperature * self.slow
+        }
+    }
+}
+
+/// Follows `inner`, but multiplies the temperature by `factor` whenever an
+/// epoch accepts less than `threshold` of its proposals, at most
+/// `max_reheats` times.
+#[derive(Clone, Debug)]
+pub struct Reheating<S> {
+    pub inner: S,
+    pub threshold: f64,
+    pub factor: f64,
+    pub max_reheats: usize,
+    reheats: usize,
+}
+
+impl<S: CoolingSchedule> Reheating<S> {
+    pub fn new(inner: S, threshold: f64, factor: f64, max_reheats: usize) -> Self {
+        Reheating {
+            inner,
+            threshold,
+            factor,
+            max_reheats,
+            reheats: 0,
+        }
+    }
+}
+
+impl<S: CoolingSchedule> CoolingSchedule for Reheating<S> {
+    fn next(&mut self, temperature: f64, acceptance_rate: f64) -> f64 {
+        if acceptance_rate < self.threshold && self.reheats < self.max_reheats {
+            self.reheats += 1;
+            temperature * self.factor
+        } else {
+            self.inner.next(temperature, acceptance_rate)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(schedule: &mut dyn CoolingSchedule, rates: &[f64]) -> Vec<f64> {
+        let mut temperature = 100.0;
+        rates
+            .iter()
+            .map(|&rate| {
+                temperature = schedule.next(temperature, rate);
+                temperature
+            })
+            .collect()
+    }
+
+    #[test]
+    fn schedules_cool_down() {
+        let rates = [0.5; 20];
+        let schedules: Vec<Box<dyn CoolingSchedule>> = vec![
+            Box::new(Geometric::new(0.9)),
+            Box::new(Linear::new(10.0)),
+            Box::new(Logarithmic::new(100.0)),
+            Box::new(LundyMees::new(0.01)),
+            Box::new(Adaptive::new(0.3)),
+        ];
+        for mut schedule in schedules {
+            let temperatures = run(&mut schedule, &rates);
+            assert!(temperatures.windows(2).all(|w| w[1] <= w[0]));
+            assert!(temperatures[0] < 100.0);
+        }
+        assert_eq!(run(&mut Linear::new(10.0), &rates)[19], 0.0);
+        assert!((run(&mut LundyMees::new(0.01), &[0.5])[0] - 50.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn adaptive_cools_faster_when_accepting_a_lot() {
+        let mut schedule = Adaptive::new(0.3);
+        assert!(schedule.next(100.0, 0.9) < schedule.next(100.0, 0.1));
+    }
+
+    #[test]
+    fn reheating_is_bounded() {
+        let mut schedule = Reheating::new(Geometric::new(0.5), 0.05, 4.0, 2);
+        assert_eq!(run(&mut schedule, &[0.5, 0.0, 0.0, 0.0, 0.5]), [50.0, 200.0, 800.0, 400.0, 200.0]);
+    }
+}
@@ -1,125 +1,39 @@
-use std::collections::HashSet;
-use std::f64::consts::E;
-use std::fs::File;
-use std::io::{self, BufRead};
-use std::path::Path;
-use std::usize;
-
-use rand::seq::IteratorRandom;
+use rand::prelude::*;
 use rand::SeedableRng;
-use rand::{prelude::*, Rng};
 use rand_pcg::Pcg64;
 
-pub type Point = (f32, f32);
+pub mod acceptance;
+pub mod annealing;
+pub mod cooling;
+pub mod tabu;
+pub mod tempering;
 
-pub fn tabu_search(adj_matrix: &[Vec<usize>], tabu_capacity: usize) -> (Vec<usize>, usize) {
-    let point_count = adj_matrix.len();
-    let mut curr = get_random_permmutation(point_count);
-    let mut curr_weight: usize = permutation_weight(&curr, adj_matrix);
-    let mut best: Vec<usize> = curr.clone();
-    let mut best_weight: usize = curr_weight;
-    let mut tabu_list: HashSet<Vec<usize>> = HashSet::with_capacity(tabu_capacity); 
-    tabu_list.insert(curr.clone());
-    let mut nobetter  = 0;
-    while nobetter >= 200 || tabu_list.len() >= tabu_capacity {
-        let mut neighborhood = get_neighborhood(&curr, adj_matrix, curr_weight);
-        neighborhood.sort_by_key(|x| x.weight);
-        for candidate in neighborhood {
-            if !tabu_list.contains(&candidate.rep) {
-                if best_weight > candidate.weight {
-                    best = candidate.rep.clone();
-                    best_weight = candidate.weight;
-                }
-                tabu_list.insert(candidate.rep.clone());
-                if curr_weight > candidate.weight {
-                    nobetter += 1;
-                } else {
-                    nobetter = 0;
-                }
-                curr = candidate.rep;
-                curr_weight = candidate.weight;
-                break;
-            }
-        }
-    }
-    (best, best_weight)
-}
+pub use acceptance::{AcceptanceCriterion, Annealing, GreatDeluge, LateAcceptance, Metropolis, RecordToRecord, ThresholdAccepting};
+pub use annealing::{accepting_search, calibrate_temperature, random_move, simulated_annealing_with, AnnealingParams, Neighborhood, FINAL_ACCEPTANCE, INITIAL_ACCEPTANCE};
+pub use cooling::{Adaptive, CoolingSchedule, Geometric, Linear, Logarithmic, LundyMees, Reheating};
+pub use tabu::{tabu_search_with, TabuParams};
+pub use tempering::{exchange_probability, parallel_tempering, temperature_ladder};
+pub use tsp_core::{held_karp_bound, instance_to_matrix, is_symmetric, permutation_weight, read_tour, write_tour, Distance, FlatMatrix, Instance, Tour, HELD_KARP_ITERATIONS};
 
-#[derive(Eq, Clone)]
-struct Neighour {
-    rep: Vec<usize>,
-    weight: usize,
-}
-impl PartialEq for Neighour {
-    fn eq(&self, other: &Self) -> bool {
-        self.weight == other.weight
-    }
-}
-impl PartialOrd for Neighour {
-    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        Some(self.cmp(other))
-    }
-}
-impl Ord for Neighour {
-    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        match self.partial_cmp(other) {
-            Some(x) => x,
-            None => panic!("Something went wrong"),
-        }
-        
-    }
+pub fn tabu_search<D: Distance + ?Sized>(adj_matrix: &D, tenure: usize) -> (Vec<usize>, usize) {
+    tabu_search_from(adj_matrix, get_random_permmutation(adj_matrix.point_count()), tenure)
 }
 
-fn get_neighborhood(
-    permutation: &Vec<usize>,
-    adj_matrix: &[Vec<usize>],
-    weight: usize,
-) -> Vec<Neighour> {
-    let length = permutation.len();
-    let mut neighborhood: Vec<Neighour> = Vec::new();
-    for diff in 1..(length/2) {
-        for j in diff..length {
-            let mut rep = permutation.clone();
-            rep[(j - diff)..=j].reverse();
-            neighborhood.push(Neighour{rep, weight: invert_weight(permutation, adj_matrix, j - diff, j, weight)});
-        }
-    }
-    neighborhood
+pub fn tabu_search_from<D: Distance + ?Sized>(adj_matrix: &D, curr: Vec<usize>, tenure: usize) -> (Vec<usize>, usize) {
+    tabu_search_with(adj_matrix, curr, &TabuParams::new(tenure))
 }
 
-fn invert_weight(
-    permutation: &Vec<usize>,
-    adj_matrix: &[Vec<usize>],
-    i: usize,
-    j: usize,
-    weight: usize,
-) -> usize {
-    let last = permutation.len() - 1;
-    let pre = i.checked_sub(1).unwrap_or(last);
-    let post = (j + 1) % permutation.len();
-    weight - adj_matrix[permutation[i]][permutation[pre]] - adj_matrix[permutation[j]][permutation[post]] + adj_matrix[permutation[j]][permutation[pre]] + adj_matrix[permutation[i]][permutation[post]]
+pub fn simulated_annealing<D: Distance + ?Sized>(adj_matrix: &D, temperature: Option<f64>, epoch_count: usize) -> (Vec<usize>, usize) {
+    simulated_annealing_from(adj_matrix, get_random_permmutation(adj_matrix.point_count()), temperature, epoch_count)
 }
 
-
-pub fn simulated_annealing(adj_matrix: &[Vec<usize>], mut temperature: usize, epoch_count: usize) -> (Vec<usize>, usize) {
-    let point_count = adj_matrix.len();
-    let mut solution = get_random_permmutation(point_count);
-    let mut current_weight = permutation_weight(&solution, adj_matrix);
-    let mut rng = Pcg64::from_entropy();
-    while temperature != 0 {
-        for _epoch in 0..epoch_count {
-            let swap_idx = (0..point_count).choose_multiple(&mut rng, 2);
-            let mut potential_solution = solution.clone();
-            potential_solution.swap(swap_idx[0], swap_idx[1]);
-            let potenital_weight = permutation_weight(&potential_solution, adj_matrix);
-            if potenital_weight < current_weight || rng.gen_bool(E.powf((current_weight as f64 - potenital_weight as f64) / temperature as f64)) {
-                current_weight = potenital_weight;
-                solution = potential_solution;
-            }
-        }
-        temperature = (temperature as f64 * 0.94) as usize;
-    }
-    (solution, current_weight)
+/// Geometric cooling from `temperature`, or from a calibrated one when `None`.
+pub fn simulated_annealing_from<D: Distance + ?Sized>(adj_matrix: &D, solution: Vec<usize>, temperature: Option<f64>, epoch_count: usize) -> (Vec<usize>, usize) {
+    let params = match temperature {
+        Some(temperature) => AnnealingParams::new(temperature, epoch_count),
+        None => AnnealingParams::calibrated(epoch_count),
+    };
+    simulated_annealing_with(adj_matrix, solution, &params, &mut Geometric::new(0.94))
 }
 
 fn get_random_permmutation(point_count: usize) -> Vec<usize> {
@@ -128,60 +42,6 @@ fn get_random_permmutation(point_count: usize) -> Vec<usize> {
     permutation
 }
 
-pub fn read_lines<P>(filename: P) -> io::Result<io::Lines<io::BufReader<File>>>
-where
-    P: AsRef<Path>,
-{
-    let file = File::open(filename)?;
-    Ok(io::BufReader::new(file).lines())
-}
-
-pub fn file_to_points<P>(filename: P) -> Vec<Point>
-where
-    P: AsRef<Path>,
-{
-    let mut points: Vec<Point> = Vec::new();
-    if let Ok(lines) = read_lines(filename) {
-        for line in lines.skip(8).flatten() {
-            if line == "EOF" {
-                break;
-            }
-            let tmp = line.split_whitespace().collect::<Vec<&str>>();
-            points.push((tmp[1].parse().unwrap(), tmp[2].parse().unwrap()));
-        }
-    }
-    points
-}
-
-pub fn points_to_matrix(points: Vec<(f32, f32)>) -> Vec<Vec<usize>> {
-    let point_count = points.len();
-    let mut adj_matrix: Vec<Vec<usize>> = vec![vec![0; point_count]; point_count];
-    for i in 0..point_count {
-        for j in i..point_count {
-            if j != i {
-                let p1 = points[i];
-                let p2 = points[j];
-                let dist =
-                    (((p1.0 - p2.0).powi(2) + (p1.1 - p2.1).powi(2)).sqrt()).round() as usize;
-                adj_matrix[i][j] = dist;
-                adj_matrix[j][i] = dist;
-            }
-        }
-    }
-    adj_matrix
-}
-
-pub fn permutation_weight(permutation: &[usize], adj_matrix: &[Vec<usize>]) -> usize {
-    let mut s: usize = 0;
-    let mut prev = permutation[0];
-    for cur in permutation.iter().skip(1) {
-        s += adj_matrix[prev][*cur];
-        prev = *cur;
-    }
-    s += *adj_matrix[0].last().unwrap();
-    s
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -189,19 +49,82 @@ mod tests {
     #[test]
     fn sa_test() {
         for path in ["test_data/1.tsp", "test_data/2.tsp", "test_data/3.tsp"] {
-            let points = file_to_points(path);
-            let point_count = points.len();
-            let adj_matrix = points_to_matrix(points);
-            let _sa = simulated_annealing(&adj_matrix, point_count, 1000);
+            let instance = Instance::from_file(path).unwrap();
+            let point_count = instance.dimension;
+            let adj_matrix = instance_to_matrix(&instance);
+            let _sa = simulated_annealing(&adj_matrix, Some(point_count as f64), 1000);
         }
     }
     #[test]
+    fn zero_temperature_test() {
+        // Every move on equal distances keeps the weight unchanged.
+        let adj_matrix = vec![vec![1; 6]; 6];
+        let (_, weight) = simulated_annealing(&adj_matrix, Some(0.0), 10);
+        assert_eq!(weight, 6);
+    }
+    #[test]
     fn ts_test() {
         for path in ["test_data/1.tsp", "test_data/2.tsp", "test_data/3.tsp"] {
-            let points = file_to_points(path);
-            let l = points.len();
-            let adj_matrix = points_to_matrix(points);
+            let instance = Instance::from_file(path).unwrap();
+            let l = instance.dimension;
+            let adj_matrix = instance_to_matrix(&instance);
             let _x = tabu_search(&adj_matrix, l);
         }
     }
+    #[test]
+    fn explicit_instance_test() {
+        let data = "NAME : m5\nTYPE : TSP\nDIMENSION : 5\nEDGE_WEIGHT_TYPE : EXPLICIT\n\
+                    EDGE_WEIGHT_FORMAT : LOWER_DIAG_ROW\nEDGE_WEIGHT_SECTION\n\
+                    0\n3 0\n4 5 0\n2 6 3 0\n7 3 4 5 0\nEOF\n";
+        let instance = Instance::parse(data.as_bytes()).unwrap();
+        let adj_matrix = instance_to_matrix(&instance);
+        assert_eq!(adj_matrix[1][4], 3);
+        let (sa, _) = simulated_annealing(&adj_matrix, None, 100);
+        let (ts, _) = tabu_search(&adj_matrix, 5);
+        assert_eq!(sa.len(), 5);
+        assert_eq!(ts.len(), 5);
+    }
+    #[test]
+    fn seeded_from_tour_file_test() {
+        let instance = Instance::from_file("test_data/1.tsp").unwrap();
+        let adj_matrix = instance_to_matrix(&instance);
+        let (tour, weight) = tabu_search(&adj_matrix, instance.dimension);
+        let path = std::env::temp_dir().join(format!("l3_{}.tour", std::process::id()));
+        write_tour(&path, &instance.name, &format!("Length {weight}"), &tour).unwrap();
+        let seed = read_tour(&path).unwrap();
+        std::fs::remove_file(path).unwrap();
+        assert_eq!(seed, tour);
+        let (ts, ts_weight) = tabu_search_from(&adj_matrix, seed.clone(), instance.dimension);
+        assert!(ts_weight <= weight);
+        assert_eq!(ts.len(), instance.dimension);
+        let (sa, _) = simulated_annealing_from(&adj_matrix, seed, Some(10.0), 100);
+        assert_eq!(sa.len(), instance.dimension);
+    }
+    #[test]
+    fn atsp_tabu_test() {
+        let data = "NAME : atsp5\nTYPE : ATSP\nDIMENSION : 5\nEDGE_WEIGHT_TYPE : EXPLICIT\n\
+                    EDGE_WEIGHT_FORMAT : FULL_MATRIX\nEDGE_WEIGHT_SECTION\n\
+                    0 3 17 25 8\n21 0 4 33 12\n6 30 0 2 27\n14 8 35 0 5\n31 19 7 26 0\nEOF\n";
+        let adj_matrix = instance_to_matrix(&Instance::parse(data.as_bytes()).unwrap());
+        assert!(!is_symmetric(&adj_matrix));
+        let cycle_weight = |p: &[usize]| -> usize { (0..5).map(|k| adj_matrix[p[k]][p[(k + 1) % 5]]).sum() };
+        let permutation = vec![3, 0, 4, 1, 2];
+        let (tour, weight) = tabu_search_from(&adj_matrix, permutation.clone(), 2);
+        assert_eq!(weight, cycle_weight(&tour));
+        assert!(weight <= cycle_weight(&permutation));
+    }
+    #[test]
+    fn distance_backends_test() {
+        use tsp_core::{CoordinateDistance, PackedMatrix};
+        let instance = Instance::from_file("test_data/1.tsp").unwrap();
+        let adj_matrix = instance_to_matrix(&instance);
+        let packed = PackedMatrix::from_instance(&instance).unwrap();
+        let cached = CoordinateDistance::with_cache(&instance, 4096);
+        let seed: Vec<usize> = (0..instance.dimension).rev().collect();
+        let expected = tabu_search_from(&adj_matrix, seed.clone(), 10);
+        assert_eq!(tabu_search_from(&packed, seed.clone(), 10), expected);
+        assert_eq!(tabu_search_from(&cached, seed.clone(), 10), expected);
+        let (sa, weight) = simulated_annealing_from(&cached, seed, None, 1000);
+        assert_eq!(weight, permutation_weight(&sa, &adj_matrix));
+    }
 }
@@ -0,0 +1,124 @@
+use std::time::{Duration, Instant};
+
+use tsp_core::moves::invert_weight;
+use tsp_core::{is_symmetric, permutation_weight, Distance};
+
+/// Stopping rules and tenure for `tabu_search_with`.
+#[derive(Clone, Debug)]
+pub struct TabuParams {
+    /// Iterations during which a removed edge may not be added back.
+    pub tenure: usize,
+    /// Iterations without improving the best tour before giving up.
+    pub max_stagnation: usize,
+    pub max_iterations: usize,
+    pub time_limit: Option<Duration>,
+}
+
+impl TabuParams {
+    pub fn new(tenure: usize) -> Self {
+        TabuParams {
+            tenure,
+            max_stagnation: 200,
+            max_iterations: 10_000,
+            time_limit: None,
+        }
+    }
+}
+
+/// Best-improvement 2-opt search that forbids re-adding the edges removed by
+/// recent moves. A tabu move is still taken when it beats the best tour found
+/// so far (aspiration).
+pub fn tabu_search_with<D: Distance + ?Sized>(adj_matrix: &D, mut curr: Vec<usize>, params: &TabuParams) -> (Vec<usize>, usize) {
+    let start = Instant::now();
+    let length = curr.len();
+    let symmetric = is_symmetric(adj_matrix);
+    let mut curr_weight = permutation_weight(&curr, adj_matrix);
+    let mut best = curr.clone();
+    let mut best_weight = curr_weight;
+    if length < 4 {
+        return (best, best_weight);
+    }
+    // tabu_until[a][b]: first iteration at which edge a -> b may be added again.
+    let mut tabu_until: Vec<Vec<usize>> = vec![vec![0; length]; length];
+    let mut stagnation = 0;
+    let mut iteration = 0;
+    while iteration < params.max_iterations && stagnation < params.max_stagnation {
+        if params.time_limit.is_some_and(|limit| start.elapsed() >= limit) {
+            break;
+        }
+        let mut chosen: Option<(usize, usize, usize)> = None;
+        for i in 0..length - 1 {
+            for j in (i + 1)..length {
+                // Inverting the whole tour only changes its direction.
+                if i == 0 && j == length - 1 {
+                    continue;
+                }
+                let weight = invert_weight(&curr, adj_matrix, i, j, curr_weight, symmetric);
+                if chosen.is_some_and(|(_, _, w)| w <= weight) {
+                    continue;
+                }
+                let pre = curr[(i + length - 1) % length];
+                let post = curr[(j + 1) % length];
+                let tabu = tabu_until[pre][curr[j]] > iteration || tabu_until[curr[i]][post] > iteration;
+                if !tabu || weight < best_weight {
+                    chosen = Some((i, j, weight));
+                }
+            }
+        }
+        let Some((i, j, weight)) = chosen else {
+            break;
+        };
+        let pre = curr[(i + length - 1) % length];
+        let post = curr[(j + 1) % length];
+        for (a, b) in [(pre, curr[i]), (curr[j], post)] {
+            tabu_until[a][b] = iteration + params.tenure + 1;
+            if symmetric {
+                tabu_until[b][a] = iteration + params.tenure + 1;
+            }
+        }
+        curr[i..=j].reverse();
+        curr_weight = weight;
+        if curr_weight < best_weight {
+            best.clone_from(&curr);
+            best_weight = curr_weight;
+            stagnation = 0;
+        } else {
+            stagnation += 1;
+        }
+        iteration += 1;
+    }
+    (best, best_weight)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tsp_core::{instance_to_matrix, Instance};
+
+    #[test]
+    fn tabu_search_improves_and_reports_true_weight() {
+        let instance = Instance::from_file("test_data/1.tsp").unwrap();
+        let adj_matrix = instance_to_matrix(&instance);
+        let start: Vec<usize> = (0..instance.dimension).map(|k| (k * 37) % instance.dimension).collect();
+        let start_weight = permutation_weight(&start, &adj_matrix);
+        let (tour, weight) = tabu_search_with(&adj_matrix, start, &TabuParams::new(10));
+        assert!(weight < start_weight);
+        assert_eq!(weight, permutation_weight(&tour, &adj_matrix));
+        let mut sorted = tour.clone();
+        sorted.sort();
+        assert!(sorted.into_iter().eq(0..instance.dimension));
+    }
+
+    #[test]
+    fn tabu_search_respects_limits() {
+        let instance = Instance::from_file("test_data/1.tsp").unwrap();
+        let adj_matrix = instance_to_matrix(&instance);
+        let start: Vec<usize> = (0..instance.dimension).collect();
+        let params = TabuParams { max_iterations: 0, ..TabuParams::new(10) };
+        let (tour, _) = tabu_search_with(&adj_matrix, start.clone(), &params);
+        assert_eq!(tour, start);
+        let params = TabuParams { time_limit: Some(Duration::ZERO), ..TabuParams::new(10) };
+        let (tour, _) = tabu_search_with(&adj_matrix, start.clone(), &params);
+        assert_eq!(tour, start);
+    }
+}
@@ -0,0 +1,110 @@
+use rand::Rng;
+use rand::SeedableRng;
+use rand_pcg::Pcg64;
+use rayon::prelude::*;
+
+use crate::acceptance::Metropolis;
+use crate::annealing::{calibrate_temperature, run_epoch, AnnealingParams, SearchState, FINAL_ACCEPTANCE, INITIAL_ACCEPTANCE};
+use tsp_core::{is_symmetric, permutation_weight, Distance};
+
+struct Replica {
+    state: SearchState,
+    metropolis: Metropolis,
+    rng: Pcg64,
+}
+
+/// `replicas` temperatures spread geometrically from `coldest` to `hottest`.
+pub fn temperature_ladder(coldest: f64, hottest: f64, replicas: usize) -> Vec<f64> {
+    if replicas < 2 {
+        return vec![coldest; replicas];
+    }
+    let ratio = (hottest / coldest).powf(1.0 / (replicas - 1) as f64);
+    (0..replicas).map(|k| coldest * ratio.powi(k as i32)).collect()
+}
+
+/// Metropolis test for exchanging the states of two replicas.
+pub fn exchange_probability(cold: f64, hot: f64, cold_weight: usize, hot_weight: usize) -> f64 {
+    ((1.0 / cold - 1.0 / hot) * (cold_weight as f64 - hot_weight as f64)).exp().min(1.0)
+}
+
+/// Replica-exchange annealing: `replicas` copies of `solution` run
+/// `params.epoch_count` proposals each at fixed temperatures between
+/// `params.final_temperature` and `params.temperature` (calibrated when
+/// `None`), in parallel, then neighbouring temperatures try to swap states.
+/// Stops after `params.max_epochs` such rounds and returns the best tour seen.
+pub fn parallel_tempering<D: Distance + Sync + ?Sized>(adj_matrix: &D, solution: Vec<usize>, params: &AnnealingParams, replicas: usize) -> (Vec<usize>, usize) {
+    let weight = permutation_weight(&solution, adj_matrix);
+    if solution.len() < 4 || replicas == 0 || params.epoch_count == 0 {
+        return (solution, weight);
+    }
+    let symmetric = is_symmetric(adj_matrix);
+    let mut rng = Pcg64::from_entropy();
+    let hottest = params
+        .temperature
+        .unwrap_or_else(|| calibrate_temperature(adj_matrix, &solution, params.neighborhood, INITIAL_ACCEPTANCE, &mut rng));
+    let coldest = params
+        .final_temperature
+        .unwrap_or_else(|| calibrate_temperature(adj_matrix, &solution, params.neighborhood, FINAL_ACCEPTANCE, &mut rng));
+    let temperatures = temperature_ladder(coldest, hottest, replicas);
+    let mut states: Vec<Replica> = temperatures
+        .iter()
+        .map(|&temperature| Replica {
+            state: SearchState::new(solution.clone(), adj_matrix),
+            metropolis: Metropolis::new(temperature),
+            rng: Pcg64::from_rng(&mut rng).unwrap(),
+        })
+        .collect();
+    for round in 0..params.max_epochs {
+        states.par_iter_mut().for_each(|replica| {
+            run_epoch(adj_matrix, &mut replica.state, &mut replica.metropolis, params, symmetric, &mut replica.rng);
+        });
+        // Alternate between even and odd pairs so every pair gets a chance.
+        for k in ((round % 2)..replicas.saturating_sub(1)).step_by(2) {
+            let p = exchange_probability(temperatures[k], temperatures[k + 1], states[k].state.weight, states[k + 1].state.weight);
+            if rng.gen_bool(p) {
+                let (cold, hot) = states.split_at_mut(k + 1);
+                std::mem::swap(&mut cold[k].state.solution, &mut hot[0].state.solution);
+                std::mem::swap(&mut cold[k].state.weight, &mut hot[0].state.weight);
+            }
+        }
+    }
+    let best = states.into_iter().map(|replica| replica.state).min_by_key(|state| state.best_weight).unwrap();
+    (best.best, best.best_weight)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::annealing::Neighborhood;
+    use tsp_core::{instance_to_matrix, Instance};
+
+    #[test]
+    fn ladder_spans_both_ends() {
+        let ladder = temperature_ladder(1.0, 1000.0, 4);
+        assert_eq!(ladder.len(), 4);
+        assert!((ladder[0] - 1.0).abs() < 1e-9);
+        assert!((ladder[1] - 10.0).abs() < 1e-9);
+        assert!((ladder[3] - 1000.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn better_hot_state_always_moves_down() {
+        assert_eq!(exchange_probability(1.0, 10.0, 500, 400), 1.0);
+        let p = exchange_probability(1.0, 10.0, 400, 401);
+        assert!((p - (-0.9_f64).exp()).abs() < 1e-12);
+    }
+
+    #[test]
+    fn parallel_tempering_improves_and_reports_true_weight() {
+        let instance = Instance::from_file("test_data/1.tsp").unwrap();
+        let adj_matrix = instance_to_matrix(&instance);
+        let start: Vec<usize> = (0..instance.dimension).map(|k| (k * 37) % instance.dimension).collect();
+        let params = AnnealingParams { neighborhood: Neighborhood::TwoOpt, max_epochs: 200, ..AnnealingParams::calibrated(500) };
+        let (tour, weight) = parallel_tempering(&adj_matrix, start.clone(), &params, 4);
+        assert_eq!(weight, permutation_weight(&tour, &adj_matrix));
+        assert!(weight < permutation_weight(&start, &adj_matrix));
+        let mut sorted = tour.clone();
+        sorted.sort();
+        assert!(sorted.into_iter().eq(0..instance.dimension));
+    }
+}
@@ -1,12 +1,12 @@
-use std::fs::File;
-use std::io::{self, BufRead};
-use std::path::Path;
 
 use rand::prelude::*;
 use rand_pcg::Pcg64Mcg;
 use rayon::prelude::*;
 
-pub type Point = (f32, f32);
+use tsp_core::mst::{candidate_prim, dfs, parent_to_adj_list};
+use tsp_core::nearest_neighbours;
+
+pub use tsp_core::{candidate_lists, held_karp_bound, instance_to_matrix, permutation_weight, read_tour, write_tour, Distance, FlatMatrix, Instance, LinKernighan, Tour, HELD_KARP_ITERATIONS, MST_CANDIDATES};
 
 #[derive(Clone, Debug)]
 struct Individual {
@@ -14,17 +14,28 @@ struct Individual {
     fitness: usize,
 }
 
-pub struct Evolution {
+/// Island-model GA over any `Distance` backend; the matrix is shared by the
+/// islands, which evolve in parallel.
+pub struct Evolution<D = Vec<Vec<usize>>> {
     islands: Vec<Vec<Individual>>,
-    adj_matrix: Vec<Vec<usize>>,
+    adj_matrix: D,
     rng: Pcg64Mcg,
+    lk: Option<LinKernighan>,
 }
 
-impl Evolution {
-    pub fn new(island_count: usize, point_count: usize, adj_matrix: Vec<Vec<usize>>) -> Self {
+impl<D: Distance + Sync> Evolution<D> {
+    /// Seeds every island with an MST tour, its tree grown from each city's
+    /// `MST_CANDIDATES` nearest neighbours.
+    pub fn new(island_count: usize, point_count: usize, adj_matrix: D) -> Self {
+        let candidates = nearest_neighbours(&adj_matrix, MST_CANDIDATES);
+        Self::with_candidates(island_count, point_count, adj_matrix, &candidates)
+    }
+    /// Candidate lists computed elsewhere, e.g. by `candidate_lists`, which
+    /// needs no pass over the whole matrix.
+    pub fn with_candidates(island_count: usize, point_count: usize, adj_matrix: D, candidates: &[Vec<usize>]) -> Self {
         let mut islands: Vec<Vec<Individual>> = Vec::with_capacity(island_count);
         let mut rng = Pcg64Mcg::from_entropy();
-        let mst = gen_mst(&adj_matrix, point_count);
+        let mst = gen_mst(&adj_matrix, candidates);
         let fitness_mst = permutation_weight(&mst, &adj_matrix);
         let mst_individual = Individual { chromosome: mst, fitness: fitness_mst };
         let mut chromosome: Vec<usize> = (0..point_count).collect();
@@ -42,6 +53,22 @@ impl Evolution {
             islands,
             adj_matrix,
             rng,
+            lk: None,
+        }
+    }
+    /// Polishes the best individual of every island with Lin–Kernighan
+    /// whenever migrants are exchanged. A no-op on asymmetric matrices, which
+    /// `LinKernighan::improve` leaves alone.
+    pub fn enable_lk(&mut self, neighbours: usize) {
+        self.lk = Some(LinKernighan::new(&self.adj_matrix, neighbours));
+    }
+    pub fn seed(&mut self, chromosome: Vec<usize>) {
+        let fitness = permutation_weight(&chromosome, &self.adj_matrix);
+        let individual = Individual { chromosome, fitness };
+        for island in &mut self.islands {
+            if let Some(worst) = island.iter_mut().max_by_key(|x| x.fitness) {
+                *worst = individual.clone();
+            }
         }
     }
     pub fn run(&mut self, pmx: bool) {
@@ -52,6 +79,7 @@ impl Evolution {
             self.selection();
             if generation % 100 == 0 {
                 self.migration();
+                self.improve_best();
                 //println!("Generation: {}", generation);
             }
             self.reproduction(pmx);
@@ -79,6 +107,18 @@ impl Evolution {
         }
         (best.chromosome, best.fitness)
     }
+    fn improve_best(&mut self) {
+        let Some(lk) = &self.lk else {
+            return;
+        };
+        let adj_matrix = &self.adj_matrix;
+        self.islands.par_iter_mut().for_each(|island| {
+            if let Some(best) = island.iter_mut().min_by_key(|x| x.fitness) {
+                let (chromosome, _, fitness) = lk.improve(std::mem::take(&mut best.chromosome), adj_matrix);
+                *best = Individual { chromosome, fitness };
+            }
+        });
+    }
     fn reproduction(&mut self, pmx: bool) {
         self.islands.par_iter_mut().for_each(|op| {
             let mut new_population: Vec<Individual> = Vec::with_capacity(100);
@@ -138,7 +178,7 @@ impl Evolution {
             }
         }
     }
-    fn pmx_crossover(rng: &mut impl Rng, parent1: &Vec<usize>, parent2: &Vec<usize>) -> (Vec<usize>, Vec<usize>) {
+    fn pmx_crossover(rng: &mut impl Rng, parent1: &[usize], parent2: &[usize]) -> (Vec<usize>, Vec<usize>) {
         let len = parent1.len();
         let crossover_point1 = rng.gen_range(0..len);
         let crossover_point2 = rng.gen_range(0..len);
@@ -148,8 +188,8 @@ impl Evolution {
             (crossover_point2, crossover_point1)
         };
     
-        let mut child1 = parent1.clone();
-        let mut child2 = parent2.clone();
+        let mut child1 = parent1.to_vec();
+        let mut child2 = parent2.to_vec();
     
         child1[start..end].clone_from_slice(&parent2[start..end]);
         child2[start..end].clone_from_slice(&parent1[start..end]);
@@ -170,7 +210,7 @@ impl Evolution {
         (child1, child2)
     }
 
-    fn cx_crossover(parent1: &Vec<usize>, parent2: &Vec<usize>) -> (Vec<usize>, Vec<usize>) {
+    fn cx_crossover(parent1: &[usize], parent2: &[usize]) -> (Vec<usize>, Vec<usize>) {
         let len = parent1.len();
         let mut child1 = vec![None; len];
         let mut child2 = vec![None; len];
@@ -196,7 +236,7 @@ impl Evolution {
     
         (child1.into_iter().map(|x| x.unwrap()).collect(), child2.into_iter().map(|x| x.unwrap()).collect())
     }
-    fn mutation(rng: &mut impl Rng, child: &mut Vec<usize>) {
+    fn mutation(rng: &mut impl Rng, child: &mut [usize]) {
         if child.len() < 2 {
             return;
         }
@@ -206,133 +246,57 @@ impl Evolution {
     }
 }
 
-pub fn permutation_weight(permutation: &[usize], adj_matrix: &[Vec<usize>]) -> usize {
-    let result = permutation.par_chunks(1000)
-        .map(|permutation_chunk| {
-            let mut s: usize = 0;
-            let mut prev = permutation_chunk[0];
-            for cur in permutation_chunk.iter().skip(1) {
-                s += adj_matrix[prev][*cur];
-                prev = *cur;
-            }
-            s += *adj_matrix[0].last().unwrap();
-            s
-        })
-        .sum();
-
-    result
-}
-
-pub fn read_lines<P>(filename: P) -> io::Result<io::Lines<io::BufReader<File>>>
-where
-    P: AsRef<Path>,
-{
-    let file = File::open(filename)?;
-    Ok(io::BufReader::new(file).lines())
-}
-
-pub fn file_to_points<P>(filename: P) -> Vec<Point>
-where
-    P: AsRef<Path>,
-{
-    let mut points: Vec<Point> = Vec::new();
-    if let Ok(lines) = read_lines(filename) {
-        for line in lines.skip(8).flatten() {
-            if line == "EOF" {
-                break;
-            }
-            let tmp = line.split_whitespace().collect::<Vec<&str>>();
-            points.push((tmp[1].parse().unwrap(), tmp[2].parse().unwrap()));
-        }
-    }
-    points
-}
-
-pub fn points_to_matrix(points: Vec<(f32, f32)>) -> Vec<Vec<usize>> {
-    let point_count = points.len();
-    let mut adj_matrix: Vec<Vec<usize>> = vec![vec![0; point_count]; point_count];
-    for i in 0..point_count {
-        for j in i..point_count {
-            if j != i {
-                let p1 = points[i];
-                let p2 = points[j];
-                let dist =
-                    (((p1.0 - p2.0).powi(2) + (p1.1 - p2.1).powi(2)).sqrt()).round() as usize;
-                adj_matrix[i][j] = dist;
-                adj_matrix[j][i] = dist;
-            }
-        }
-    }
-    adj_matrix
-}
-
-fn gen_mst(adj_matrix: &[Vec<usize>], point_count: usize) -> Vec<usize> {
-    let parent = prim(&adj_matrix, point_count);
+fn gen_mst<D: Distance + ?Sized>(adj_matrix: &D, candidates: &[Vec<usize>]) -> Vec<usize> {
+    let parent = candidate_prim(adj_matrix, candidates);
     let mst = parent_to_adj_list(&parent);
     dfs(&mst)
 }
 
-fn parent_to_adj_list(parent: &Vec<usize>) -> Vec<Vec<usize>> {
-    let mut adj_list: Vec<Vec<usize>> = vec![Vec::new(); parent.len()];
-    for (u,v) in parent.iter().enumerate().skip(1) {
-        adj_list[u].push(*v);
-        adj_list[*v].push(u);
-    }
-    adj_list
-}
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-fn dfs(graph: &Vec<Vec<usize>>) -> Vec<usize>{
-    let mut visited: Vec<usize> = Vec::new();
-    let mut traversal: Vec<usize> = Vec::new();
-    for i in 0..graph.len() {
-        if !visited.contains(&i) {
-            let mut stack: Vec<usize> = Vec::new();
-            visited.push(i);
-            stack.push(i);
-            while let Some(node) = stack.pop() {
-                traversal.push(node);
-                for j in &graph[node] {
-                    if !visited.contains(j) {
-                        visited.push(*j);
-                        stack.push(*j);
-                    }
-                }
-            }
-        }
+    #[test]
+    fn seeded_tour_survives_test() {
+        let instance = Instance::from_file("test_data/1.tsp").unwrap();
+        let adj_matrix = instance_to_matrix(&instance);
+        let mut seed: Vec<usize> = (0..instance.dimension).collect();
+        seed.sort_by_key(|&i| (instance.points[i].0 as i64, instance.points[i].1 as i64));
+        let path = std::env::temp_dir().join(format!("l4_{}.tour", std::process::id()));
+        write_tour(&path, &instance.name, "sorted by x", &seed).unwrap();
+        let seed = read_tour(&path).unwrap();
+        std::fs::remove_file(path).unwrap();
+        let seed_weight = permutation_weight(&seed, &adj_matrix);
+        let mut ga = Evolution::new(2, instance.dimension, adj_matrix);
+        ga.seed(seed);
+        assert!(ga.extract_best().1 <= seed_weight);
     }
-    traversal
-}
-
-fn prim(adj_matrix: &[Vec<usize>], point_count: usize) -> Vec<usize> {
-    let mut parent: Vec<usize> = vec![usize::MAX; point_count];
-    let mut key: Vec<usize> = vec![usize::MAX; point_count];
-    let mut mst_set: Vec<bool> = vec![false; point_count];
-
-    key[0] = 0;
-
-    for _ in 0..(point_count - 1) {
-        let u = min_key(&key, &mst_set, point_count);
-        mst_set[u] = true;
-        for v in 0..point_count {
-            if adj_matrix[u][v] != 0 && !mst_set[v] && adj_matrix[u][v] < key[v] {
-                parent[v] = u;
-                key[v] = adj_matrix[u][v];
-            }
-        }
+    #[test]
+    fn lk_polishes_island_best_test() {
+        let instance = Instance::from_file("test_data/1.tsp").unwrap();
+        let adj_matrix = instance_to_matrix(&instance);
+        let mut ga = Evolution::new(2, instance.dimension, adj_matrix.clone());
+        let before = ga.extract_best().1;
+        ga.enable_lk(8);
+        ga.improve_best();
+        let (tour, weight) = ga.extract_best();
+        assert!(weight < before);
+        assert_eq!(weight, permutation_weight(&tour, &adj_matrix));
     }
-
-    parent
-}
-
-fn min_key(key: &[usize], mst_set: &[bool], point_count: usize) -> usize {
-    let mut min = usize::MAX;
-    let mut min_index = 0;
-    for v in 0..point_count {
-        if !mst_set[v] && key[v] < min {
-            min = key[v];
-            min_index = v;
-        }
+    #[test]
+    fn flat_matrix_test() {
+        let instance = Instance::from_file("test_data/1.tsp").unwrap();
+        let adj_matrix = instance_to_matrix(&instance);
+        let flat = FlatMatrix::from_instance(&instance);
+        let (before, tour, weight) = tsp_core::with_flat_matrix!(&flat, matrix => {
+            let mut ga = Evolution::new(2, instance.dimension, matrix.clone());
+            let before = ga.extract_best().1;
+            ga.enable_lk(8);
+            ga.improve_best();
+            let (tour, weight) = ga.extract_best();
+            (before, tour, weight)
+        });
+        assert!(weight < before);
+        assert_eq!(weight, permutation_weight(&tour, &adj_matrix));
     }
-    min_index
-} 
-
+}
@@ -1,66 +1,29 @@
 use std::time::Instant;
 use std::sync::{Arc, Mutex};
 use std::thread;
-use std::fs::File;
+use std::fs::{self, File};
 use std::io::Write;
 use l4::*;
+use tsp_core::with_flat_matrix;
 
 fn main() {
-    let points = file_to_points("test_data/1.tsp");
-    let point_count = points.len();
-    let adj_matrix = points_to_matrix(points);
-    let avg_time: Arc<Mutex<f64>> = Arc::new(Mutex::new(0.0));
-    let avg_weight: Arc<Mutex<f64>> = Arc::new(Mutex::new(0.0));
-    let mut handles = Vec::new();
-    for _ in 0..10 {
-        let avg_time = Arc::clone(&avg_time);
-        let avg_weight = Arc::clone(&avg_weight);
-        let adj_matrix = adj_matrix.clone();
-        let handle = thread::spawn(move || {
-            let start = Instant::now();
-            let mut ga = Evolution::new(4, point_count, adj_matrix);
-            ga.run(true);
-            let (_, weight) = ga.extract_best();
-            let elapsed = start.elapsed().as_secs_f64();
-            let mut time_acc = avg_time.lock().unwrap();
-            let mut weight_acc = avg_weight.lock().unwrap();
-            *time_acc += elapsed / 10.0;
-            *weight_acc += weight as f64 / 10.0;
-        });
-        handles.push(handle);
-    }
-    for handle in handles {
-        handle.join().unwrap();
-    }
-    println!("Type: PMX, Weight: {}, Time: {}", avg_weight.lock().unwrap(), avg_time.lock().unwrap());
-    
-    let avg_time: Arc<Mutex<f64>> = Arc::new(Mutex::new(0.0)); 
-    let avg_weight: Arc<Mutex<f64>> = Arc::new(Mutex::new(0.0));
-    let mut handles = Vec::new();
-    for _ in 0..10 {
-        let avg_time = Arc::clone(&avg_time);
-        let avg_weight = Arc::clone(&avg_weight);
-        let adj_matrix = adj_matrix.clone();
-        let handle = thread::spawn(move || {
-            let start = Instant::now();
-            let mut ga = Evolution::new(4, point_count, adj_matrix);
-            ga.run(false);
-            let (_, weight) = ga.extract_best();
-            let elapsed = start.elapsed().as_secs_f64();
-            let mut time_acc = avg_time.lock().unwrap();
-            let mut weight_acc = avg_weight.lock().unwrap();
-            *time_acc += elapsed / 10.0;
-            *weight_acc += weight as f64 / 10.0;
-        });
-        handles.push(handle);
-    }
-    for handle in handles {
-        handle.join().unwrap();
+    let instance = match Instance::from_file("test_data/1.tsp") {
+        Ok(instance) => instance,
+        Err(err) => {
+            eprintln!("test_data/1.tsp: {err}");
+            return;
+        }
+    };
+    let adj_matrix = FlatMatrix::from_instance(&instance);
+    let candidates = candidate_lists(&instance, MST_CANDIDATES);
+    for (name, pmx, lk) in [("PMX", true, false), ("CX", false, false), ("PMX+LK", true, true)] {
+        let (weight, time, _) = with_flat_matrix!(&adj_matrix, matrix => evolve(matrix, &candidates, 1, pmx, lk));
+        println!("Type: {name}, Weight: {weight}, Time: {time}");
     }
-    println!("Type: CX, Weight: {}, Time: {}", avg_weight.lock().unwrap(), avg_time.lock().unwrap());
 
+    fs::create_dir_all("tours").expect("Failed to create tours directory");
     let mut file = File::create("data.csv").expect("Failed to create file");
-    file.write_all(b"map;avg_weight;avg_time\n").expect("Failed to write to file");
+    file.write_all(b"map;avg_weight;avg_time;best_weight;hk_bound\n").expect("Failed to write to file");
     for path in [
         "test_data/1.tsp",
         "test_data/2.tsp",
@@ -78,35 +41,73 @@ fn main() {
         "test_data/e.tsp",
         "test_data/f.tsp",
     ] {
-        let points = file_to_points(path);
-        let point_count = points.len();
-        let adj_matrix = points_to_matrix(points);
+        let instance = match Instance::from_file(path) {
+            Ok(instance) => instance,
+            Err(err) => {
+                eprintln!("{path}: {err}");
+                continue;
+            }
+        };
+        let point_count = instance.dimension;
+        let adj_matrix = FlatMatrix::from_instance(&instance);
+        let candidates = candidate_lists(&instance, MST_CANDIDATES);
+        let (avg_weight, avg_time, (tour, weight)) =
+            with_flat_matrix!(&adj_matrix, matrix => evolve(matrix, &candidates, 10, true, false));
+        let hk_bound = with_flat_matrix!(&adj_matrix, matrix => held_karp_bound(matrix, weight, HELD_KARP_ITERATIONS));
+        file.write_all(format!("{};{};{};{};{}\n", point_count, avg_weight, avg_time, weight, hk_bound).as_bytes()).expect("Failed to write to file");
+        write_tour(format!("tours/{}.ga.tour", instance.name), &instance.name, &format!("Length {weight}"), &tour).expect("Failed to write tour");
+    }
+}
 
-        let avg_time: Arc<Mutex<f64>> = Arc::new(Mutex::new(0.0));
-        let avg_weight: Arc<Mutex<f64>> = Arc::new(Mutex::new(0.0));
+// Runs the GA `batches` times on 10 threads at once, each on its own copy of
+// the matrix. Returns the average weight and time in seconds over all runs,
+// and the best tour found.
+fn evolve<D: Distance + Clone + Send + Sync + 'static>(
+    adj_matrix: &D,
+    candidates: &[Vec<usize>],
+    batches: usize,
+    pmx: bool,
+    lk: bool,
+) -> (f64, f64, (Vec<usize>, usize)) {
+    let point_count = adj_matrix.point_count();
+    let runs = (batches * 10) as f64;
+    let avg_time: Arc<Mutex<f64>> = Arc::new(Mutex::new(0.0));
+    let avg_weight: Arc<Mutex<f64>> = Arc::new(Mutex::new(0.0));
+    let best: Arc<Mutex<(Vec<usize>, usize)>> = Arc::new(Mutex::new((Vec::new(), usize::MAX)));
+    for _ in 0..batches {
+        let mut handles = Vec::new();
         for _ in 0..10 {
-            let mut handles = Vec::new();
-            for _ in 0..10 {
-                let avg_time = Arc::clone(&avg_time);
-                let avg_weight = Arc::clone(&avg_weight);
-                let adj_matrix = adj_matrix.clone();
-                let handle = thread::spawn(move || {
-                    let start = Instant::now();
-                    let mut ga = Evolution::new(4, point_count, adj_matrix);
-                    ga.run(true);
-                    let (_, weight) = ga.extract_best();
-                    let elapsed = start.elapsed().as_secs_f64();
-                    let mut time_acc = avg_time.lock().unwrap();
-                    let mut weight_acc = avg_weight.lock().unwrap();
-                    *time_acc += elapsed / 100.0;
-                    *weight_acc += weight as f64 / 100.0;
-                });
-                handles.push(handle);
-            }
-            for handle in handles {
-                handle.join().unwrap();
-            }
+            let avg_time = Arc::clone(&avg_time);
+            let avg_weight = Arc::clone(&avg_weight);
+            let best = Arc::clone(&best);
+            let adj_matrix = adj_matrix.clone();
+            let candidates = candidates.to_vec();
+            let handle = thread::spawn(move || {
+                let start = Instant::now();
+                let mut ga = Evolution::with_candidates(4, point_count, adj_matrix, &candidates);
+                if lk {
+                    ga.enable_lk(8);
+                }
+                ga.run(pmx);
+                let (tour, weight) = ga.extract_best();
+                let elapsed = start.elapsed().as_secs_f64();
+                let mut time_acc = avg_time.lock().unwrap();
+                let mut weight_acc = avg_weight.lock().unwrap();
+                *time_acc += elapsed / runs;
+                *weight_acc += weight as f64 / runs;
+                let mut best = best.lock().unwrap();
+                if weight < best.1 {
+                    *best = (tour, weight);
+                }
+            });
+            handles.push(handle);
+        }
+        for handle in handles {
+            handle.join().unwrap();
         }
-        file.write_all(format!("{};{};{}\n", point_count, avg_weight.lock().unwrap(), avg_time.lock().unwrap()).as_bytes()).expect("Failed to write to file");
     }
+    let avg_weight = *avg_weight.lock().unwrap();
+    let avg_time = *avg_time.lock().unwrap();
+    let best = std::mem::take(&mut *best.lock().unwrap());
+    (avg_weight, avg_time, best)
 }
@@ -0,0 +1,83 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+
+use tsp_core::moves::invert_weight;
+use tsp_core::{
+    instance_to_matrix, permutation_weight, with_flat_matrix, DenseMatrix, Distance, FlatMatrix,
+    Instance,
+};
+
+/// xit1083, the size of the 1000-city runs in l3 and l4.
+const INSTANCE: &str = "../l2/test_data/b.tsp";
+
+fn shuffled(length: usize) -> Vec<usize> {
+    let mut permutation: Vec<usize> = (0..length).collect();
+    // Small LCG so the benchmark needs no rand.
+    let mut state = 12345_u64;
+    for i in (1..length).rev() {
+        state = state
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        permutation.swap(i, (state >> 33) as usize % (i + 1));
+    }
+    permutation
+}
+
+/// Every 2-opt move of the tour, as `local_search` prices them.
+fn two_opt_scan<D: Distance + ?Sized>(
+    permutation: &[usize],
+    adj_matrix: &D,
+    weight: usize,
+) -> usize {
+    let length = permutation.len();
+    let mut best = weight;
+    for i in 0..length - 1 {
+        for j in i + 1..length {
+            best = best.min(invert_weight(permutation, adj_matrix, i, j, weight, true));
+        }
+    }
+    best
+}
+
+fn bench_matrices(c: &mut Criterion) {
+    let instance = Instance::from_file(INSTANCE).unwrap();
+    let rows = instance_to_matrix(&instance);
+    let dense: DenseMatrix = DenseMatrix::from_instance(&instance);
+    let flat = FlatMatrix::from_instance(&instance);
+    let permutation = shuffled(instance.dimension);
+    let weight = permutation_weight(&permutation, &rows);
+    let bits = flat.width() * 8;
+
+    let mut group = c.benchmark_group("permutation_weight");
+    group.bench_function("rows", |b| {
+        b.iter(|| permutation_weight(black_box(&permutation), &rows))
+    });
+    group.bench_function("dense_usize", |b| {
+        b.iter(|| permutation_weight(black_box(&permutation), &dense))
+    });
+    group.bench_function(BenchmarkId::new("flat", bits), |b| {
+        b.iter(|| permutation_weight(black_box(&permutation), &flat))
+    });
+    group.bench_function(BenchmarkId::new("flat_dispatched", bits), |b| {
+        b.iter(|| with_flat_matrix!(&flat, matrix => permutation_weight(black_box(&permutation), matrix)))
+    });
+    group.finish();
+
+    let mut group = c.benchmark_group("two_opt_scan");
+    group.sample_size(20);
+    group.bench_function("rows", |b| {
+        b.iter(|| two_opt_scan(black_box(&permutation), &rows, weight))
+    });
+    group.bench_function("dense_usize", |b| {
+        b.iter(|| two_opt_scan(black_box(&permutation), &dense, weight))
+    });
+    group.bench_function(BenchmarkId::new("flat", bits), |b| {
+        b.iter(|| two_opt_scan(black_box(&permutation), &flat, weight))
+    });
+    group.bench_function(BenchmarkId::new("flat_dispatched", bits), |b| {
+        b.iter(|| with_flat_matrix!(&flat, matrix => two_opt_scan(black_box(&permutation), matrix, weight)))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_matrices);
+criterion_main!(benches);
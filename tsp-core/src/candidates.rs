@@ -0,0 +1,165 @@
+use crate::distance::Distance;
+use crate::tsplib::{EdgeWeightType, Instance, Point};
+
+/// The `k` nearest other cities of every city, closest first.
+pub fn nearest_neighbours<D: Distance + ?Sized>(adj_matrix: &D, k: usize) -> Vec<Vec<usize>> {
+    let point_count = adj_matrix.point_count();
+    (0..point_count)
+        .map(|i| {
+            let mut others: Vec<usize> = (0..point_count).filter(|&j| j != i).collect();
+            if k < others.len() {
+                others.select_nth_unstable_by_key(k, |&j| adj_matrix.dist(i, j));
+                others.truncate(k);
+            }
+            others.sort_by_key(|&j| adj_matrix.dist(i, j));
+            others
+        })
+        .collect()
+}
+
+/// `nearest_neighbours` straight from an instance, without a distance
+/// matrix. Planar instances whose weights grow with the Euclidean distance
+/// are searched with a `KdTree`; the rest compare every pair.
+pub fn candidate_lists(instance: &Instance, k: usize) -> Vec<Vec<usize>> {
+    let point_count = instance.dimension;
+    let planar = instance.weights.is_none()
+        && matches!(
+            instance.edge_weight_type,
+            EdgeWeightType::Euc2d | EdgeWeightType::Ceil2d | EdgeWeightType::Att
+        );
+    let tree = planar.then(|| KdTree::new(&instance.points));
+    (0..point_count)
+        .map(|i| {
+            let mut others = match &tree {
+                Some(tree) => tree.nearest(i, k),
+                None => {
+                    let mut others: Vec<usize> = (0..point_count).filter(|&j| j != i).collect();
+                    if k < others.len() {
+                        others.select_nth_unstable_by_key(k, |&j| instance.distance(i, j));
+                        others.truncate(k);
+                    }
+                    others
+                }
+            };
+            others.sort_by_key(|&j| instance.distance(i, j));
+            others
+        })
+        .collect()
+}
+
+/// 2-d tree over the `x`, `y` coordinates of `points`. `order` holds every
+/// subtree as a slice with its root in the middle, splitting on `x` and `y`
+/// in turn.
+pub struct KdTree<'a> {
+    points: &'a [Point],
+    order: Vec<usize>,
+}
+
+impl<'a> KdTree<'a> {
+    pub fn new(points: &'a [Point]) -> Self {
+        let mut order: Vec<usize> = (0..points.len()).collect();
+        build(points, &mut order, 0);
+        KdTree { points, order }
+    }
+
+    /// The `k` points closest to `points[city]`, other than `city` itself,
+    /// closest first.
+    pub fn nearest(&self, city: usize, k: usize) -> Vec<usize> {
+        let mut best: Vec<(f64, usize)> = Vec::with_capacity(k + 1);
+        if k > 0 {
+            self.search(&self.order, 0, city, k, &mut best);
+        }
+        best.into_iter().map(|(_, j)| j).collect()
+    }
+
+    fn search(
+        &self,
+        order: &[usize],
+        depth: usize,
+        city: usize,
+        k: usize,
+        best: &mut Vec<(f64, usize)>,
+    ) {
+        if order.is_empty() {
+            return;
+        }
+        let mid = order.len() / 2;
+        let node = order[mid];
+        let target = &self.points[city];
+        let point = &self.points[node];
+        if node != city {
+            let (dx, dy) = (target.0 - point.0, target.1 - point.1);
+            let d = dx * dx + dy * dy;
+            if best.len() < k || d < best[best.len() - 1].0 {
+                let at = best.partition_point(|&(e, _)| e <= d);
+                best.insert(at, (d, node));
+                best.truncate(k);
+            }
+        }
+        let diff = coordinate(target, depth) - coordinate(point, depth);
+        let (near, far) = if diff < 0.0 {
+            (&order[..mid], &order[mid + 1..])
+        } else {
+            (&order[mid + 1..], &order[..mid])
+        };
+        self.search(near, depth + 1, city, k, best);
+        if best.len() < k || diff * diff <= best[best.len() - 1].0 {
+            self.search(far, depth + 1, city, k, best);
+        }
+    }
+}
+
+fn coordinate(point: &Point, depth: usize) -> f64 {
+    if depth.is_multiple_of(2) {
+        point.0
+    } else {
+        point.1
+    }
+}
+
+fn build(points: &[Point], order: &mut [usize], depth: usize) {
+    if order.len() < 2 {
+        return;
+    }
+    let mid = order.len() / 2;
+    order.select_nth_unstable_by(mid, |&a, &b| {
+        coordinate(&points[a], depth).total_cmp(&coordinate(&points[b], depth))
+    });
+    let (left, right) = order.split_at_mut(mid);
+    build(points, left, depth + 1);
+    build(points, &mut right[1..], depth + 1);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nearest_neighbours_on_a_line() {
+        let xs = [0_i64, 4, 9, 15, 16];
+        let adj_matrix: Vec<Vec<usize>> = xs
+            .iter()
+            .map(|a| xs.iter().map(|b| (a - b).unsigned_abs() as usize).collect())
+            .collect();
+        let candidates = nearest_neighbours(&adj_matrix, 2);
+        assert_eq!(candidates[0], [1, 2]);
+        assert_eq!(candidates[2], [1, 3]);
+        assert_eq!(candidates[4], [3, 2]);
+        assert_eq!(nearest_neighbours(&adj_matrix, 10)[1], [0, 2, 3, 4]);
+    }
+    #[test]
+    fn kd_tree_matches_matrix_neighbours() {
+        use crate::instance_to_matrix;
+        let instance = Instance::from_file("../l2/test_data/2.tsp").unwrap();
+        let adj_matrix = instance_to_matrix(&instance);
+        let from_matrix = nearest_neighbours(&adj_matrix, 10);
+        let from_tree = candidate_lists(&instance, 10);
+        for i in 0..instance.dimension {
+            let row = &adj_matrix[i];
+            // Ties may come out in either order, so compare the distances.
+            let expected: Vec<usize> = from_matrix[i].iter().map(|&j| row[j]).collect();
+            let found: Vec<usize> = from_tree[i].iter().map(|&j| row[j]).collect();
+            assert_eq!(found, expected, "city {i}");
+        }
+    }
+}
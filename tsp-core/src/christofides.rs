@@ -0,0 +1,603 @@
+use std::collections::VecDeque;
+
+use crate::distance::Distance;
+use crate::mst::prim;
+
+/// Odd-degree cities above which `min_weight_matching` goes greedy rather
+/// than run the O(m³) blossom algorithm. Its tables take (2m)² 16-byte edges
+/// plus 2m² ids: about 20 MB at this limit, and four times that at 1000. The
+/// l1 maps have at most 230 odd cities (xql662).
+pub const BLOSSOM_LIMIT: usize = 500;
+/// How many of its nearest odd cities the greedy matching offers each one.
+const MATCH_NEIGHBOURS: usize = 10;
+
+/// Christofides' tour: the MST plus a minimum-weight perfect matching of its
+/// odd-degree cities is an Eulerian multigraph, whose circuit from city 0 is
+/// shortcut at repeated cities. Within 1.5 times the optimum on metric
+/// instances as long as the matching is exact. Symmetric matrices only.
+pub fn christofides<D: Distance + ?Sized>(adj_matrix: &D) -> Vec<usize> {
+    let point_count = adj_matrix.point_count();
+    if point_count < 3 {
+        return (0..point_count).collect();
+    }
+    let parent = prim(adj_matrix, point_count);
+    let mut edges: Vec<(usize, usize)> = parent
+        .iter()
+        .enumerate()
+        .skip(1)
+        .map(|(v, &u)| (u, v))
+        .collect();
+    let mut degree = vec![0; point_count];
+    for &(u, v) in &edges {
+        degree[u] += 1;
+        degree[v] += 1;
+    }
+    let odd: Vec<usize> = (0..point_count).filter(|&i| degree[i] % 2 == 1).collect();
+    edges.extend(min_weight_matching(adj_matrix, &odd));
+    shortcut(&euler_circuit(point_count, &edges), point_count)
+}
+
+/// Pairs up `cities`, an even number of them, as cheaply as possible: the
+/// exact blossom algorithm up to `BLOSSOM_LIMIT` cities, greedy above.
+pub fn min_weight_matching<D: Distance + ?Sized>(
+    adj_matrix: &D,
+    cities: &[usize],
+) -> Vec<(usize, usize)> {
+    if cities.len() <= BLOSSOM_LIMIT {
+        blossom_matching(adj_matrix, cities)
+    } else {
+        greedy_matching(adj_matrix, cities)
+    }
+}
+
+/// Hierholzer's algorithm from city 0; every degree must be even and the
+/// multigraph connected. The circuit ends back at 0.
+fn euler_circuit(point_count: usize, edges: &[(usize, usize)]) -> Vec<usize> {
+    let mut incident: Vec<Vec<usize>> = vec![Vec::new(); point_count];
+    for (e, &(u, v)) in edges.iter().enumerate() {
+        incident[u].push(e);
+        incident[v].push(e);
+    }
+    let mut used = vec![false; edges.len()];
+    let mut circuit: Vec<usize> = Vec::with_capacity(edges.len() + 1);
+    let mut stack: Vec<usize> = vec![0];
+    while let Some(&u) = stack.last() {
+        // Edges walked from their other end are still listed here.
+        while incident[u].last().is_some_and(|&e| used[e]) {
+            incident[u].pop();
+        }
+        match incident[u].pop() {
+            Some(e) => {
+                used[e] = true;
+                let (a, b) = edges[e];
+                stack.push(if a == u { b } else { a });
+            }
+            None => circuit.push(stack.pop().unwrap()),
+        }
+    }
+    circuit
+}
+
+/// Keeps the first visit of every city.
+fn shortcut(circuit: &[usize], point_count: usize) -> Vec<usize> {
+    let mut seen = vec![false; point_count];
+    circuit
+        .iter()
+        .copied()
+        .filter(|&city| !std::mem::replace(&mut seen[city], true))
+        .collect()
+}
+
+/// Shortest edges first among each city's `MATCH_NEIGHBOURS` nearest; cities
+/// left over once their neighbours are taken pair with the nearest one left.
+fn greedy_matching<D: Distance + ?Sized>(adj_matrix: &D, cities: &[usize]) -> Vec<(usize, usize)> {
+    let count = cities.len();
+    let d = |i: usize, j: usize| adj_matrix.dist(cities[i], cities[j]);
+    let mut edges: Vec<(usize, usize, usize)> = Vec::with_capacity(count * MATCH_NEIGHBOURS);
+    for i in 0..count {
+        let mut others: Vec<usize> = (0..count).filter(|&j| j != i).collect();
+        let k = MATCH_NEIGHBOURS.min(others.len());
+        if k < others.len() {
+            others.select_nth_unstable_by_key(k, |&j| d(i, j));
+        }
+        edges.extend(others[..k].iter().map(|&j| (d(i, j), i.min(j), i.max(j))));
+    }
+    edges.sort_unstable();
+    edges.dedup();
+    let mut matched = vec![false; count];
+    let mut pairs: Vec<(usize, usize)> = Vec::with_capacity(count / 2);
+    for (_, i, j) in edges {
+        if !matched[i] && !matched[j] {
+            matched[i] = true;
+            matched[j] = true;
+            pairs.push((cities[i], cities[j]));
+        }
+    }
+    let mut left: Vec<usize> = (0..count).filter(|&i| !matched[i]).collect();
+    while let Some(i) = left.pop() {
+        let k = (0..left.len()).min_by_key(|&k| d(i, left[k])).unwrap();
+        let j = left.swap_remove(k);
+        pairs.push((cities[i], cities[j]));
+    }
+    pairs
+}
+
+/// Exact matching through a maximum-weight one: with weights `c - d` and `c`
+/// above any m/2 distances, every perfect matching outweighs every smaller
+/// one, and the heaviest perfect matching is the lightest in `d`.
+fn blossom_matching<D: Distance + ?Sized>(adj_matrix: &D, cities: &[usize]) -> Vec<(usize, usize)> {
+    let count = cities.len();
+    if count == 0 {
+        return Vec::new();
+    }
+    let mut weights = vec![vec![0; count]; count];
+    let mut longest = 0;
+    for i in 0..count {
+        for j in i + 1..count {
+            let d = adj_matrix.dist(cities[i], cities[j]) as i64;
+            weights[i][j] = d;
+            weights[j][i] = d;
+            longest = longest.max(d);
+        }
+    }
+    let c = longest * (count as i64 / 2 + 1) + 1;
+    for (i, row) in weights.iter_mut().enumerate() {
+        for (j, w) in row.iter_mut().enumerate() {
+            if i != j {
+                *w = c - *w;
+            }
+        }
+    }
+    let mut blossom = Blossom::new(&weights);
+    drop(weights);
+    while blossom.augment_once() {}
+    (1..=count)
+        .filter(|&u| blossom.mate[u] > u)
+        .map(|u| (cities[u - 1], cities[blossom.mate[u] - 1]))
+        .collect()
+}
+
+#[derive(Clone, Copy)]
+struct Edge {
+    u: u32,
+    v: u32,
+    w: i64,
+}
+
+/// Edmonds' weighted blossom algorithm with dual labels, O(n³). Vertices are
+/// `1..=n`, blossoms `n + 1..=2n` and 0 stands for none.
+struct Blossom {
+    n: usize,
+    /// Highest vertex or blossom id in use.
+    n_x: usize,
+    /// `g[a][b]` is the edge between the original vertices closest to tight
+    /// that link `a` and `b`; weight 0 means no edge.
+    g: Vec<Vec<Edge>>,
+    lab: Vec<i64>,
+    mate: Vec<usize>,
+    slack: Vec<usize>,
+    /// The outermost blossom containing each vertex or blossom.
+    st: Vec<usize>,
+    pa: Vec<usize>,
+    flower_from: Vec<Vec<usize>>,
+    /// -1 unlabelled, 0 outer, 1 inner.
+    label: Vec<i8>,
+    visited: Vec<usize>,
+    stamp: usize,
+    /// Sub-blossoms in cycle order, starting at the base.
+    flower: Vec<Vec<usize>>,
+    queue: VecDeque<usize>,
+}
+
+impl Blossom {
+    fn new(weights: &[Vec<i64>]) -> Self {
+        let n = weights.len();
+        let size = 2 * n + 1;
+        let mut g: Vec<Vec<Edge>> = (0..size)
+            .map(|u| {
+                (0..size)
+                    .map(|v| Edge {
+                        u: u as u32,
+                        v: v as u32,
+                        w: 0,
+                    })
+                    .collect()
+            })
+            .collect();
+        let mut heaviest = 0;
+        for u in 1..=n {
+            for v in 1..=n {
+                g[u][v].w = weights[u - 1][v - 1];
+                heaviest = heaviest.max(g[u][v].w);
+            }
+        }
+        let mut flower_from = vec![vec![0; n + 1]; size];
+        let mut st = vec![0; size];
+        for u in 1..=n {
+            flower_from[u][u] = u;
+            st[u] = u;
+        }
+        let mut lab = vec![0; size];
+        lab[1..=n].fill(heaviest);
+        Blossom {
+            n,
+            n_x: n,
+            g,
+            lab,
+            mate: vec![0; size],
+            slack: vec![0; size],
+            st,
+            pa: vec![0; size],
+            flower_from,
+            label: vec![-1; size],
+            visited: vec![0; size],
+            stamp: 0,
+            flower: vec![Vec::new(); size],
+            queue: VecDeque::new(),
+        }
+    }
+
+    fn slack_of(&self, e: Edge) -> i64 {
+        self.lab[e.u as usize] + self.lab[e.v as usize] - 2 * e.w
+    }
+
+    fn update_slack(&mut self, u: usize, x: usize) {
+        if self.slack[x] == 0
+            || self.slack_of(self.g[u][x]) < self.slack_of(self.g[self.slack[x]][x])
+        {
+            self.slack[x] = u;
+        }
+    }
+
+    fn set_slack(&mut self, x: usize) {
+        self.slack[x] = 0;
+        for u in 1..=self.n {
+            if self.g[u][x].w > 0 && self.st[u] != x && self.label[self.st[u]] == 0 {
+                self.update_slack(u, x);
+            }
+        }
+    }
+
+    fn push(&mut self, x: usize) {
+        if x <= self.n {
+            self.queue.push_back(x);
+        } else {
+            for i in 0..self.flower[x].len() {
+                self.push(self.flower[x][i]);
+            }
+        }
+    }
+
+    fn set_st(&mut self, x: usize, b: usize) {
+        self.st[x] = b;
+        if x > self.n {
+            for i in 0..self.flower[x].len() {
+                self.set_st(self.flower[x][i], b);
+            }
+        }
+    }
+
+    /// Position of `xr` in blossom `b`, flipping the cycle so that the path
+    /// from the base to it has even length.
+    fn even_position(&mut self, b: usize, xr: usize) -> usize {
+        let pr = self.flower[b].iter().position(|&x| x == xr).unwrap();
+        if pr % 2 == 1 {
+            self.flower[b][1..].reverse();
+            self.flower[b].len() - pr
+        } else {
+            pr
+        }
+    }
+
+    fn set_match(&mut self, u: usize, v: usize) {
+        let e = self.g[u][v];
+        self.mate[u] = e.v as usize;
+        if u > self.n {
+            let xr = self.flower_from[u][e.u as usize];
+            let pr = self.even_position(u, xr);
+            for i in 0..pr {
+                self.set_match(self.flower[u][i], self.flower[u][i ^ 1]);
+            }
+            self.set_match(xr, v);
+            self.flower[u].rotate_left(pr);
+        }
+    }
+
+    fn augment(&mut self, mut u: usize, mut v: usize) {
+        loop {
+            let xnv = self.st[self.mate[u]];
+            self.set_match(u, v);
+            if xnv == 0 {
+                return;
+            }
+            self.set_match(xnv, self.st[self.pa[xnv]]);
+            u = self.st[self.pa[xnv]];
+            v = xnv;
+        }
+    }
+
+    fn lowest_common_ancestor(&mut self, mut u: usize, mut v: usize) -> usize {
+        self.stamp += 1;
+        while u != 0 || v != 0 {
+            if u != 0 {
+                if self.visited[u] == self.stamp {
+                    return u;
+                }
+                self.visited[u] = self.stamp;
+                u = self.st[self.mate[u]];
+                if u != 0 {
+                    u = self.st[self.pa[u]];
+                }
+            }
+            std::mem::swap(&mut u, &mut v);
+        }
+        0
+    }
+
+    fn add_blossom(&mut self, u: usize, lca: usize, v: usize) {
+        let mut b = self.n + 1;
+        while b <= self.n_x && self.st[b] != 0 {
+            b += 1;
+        }
+        if b > self.n_x {
+            self.n_x += 1;
+        }
+        self.lab[b] = 0;
+        self.label[b] = 0;
+        self.mate[b] = self.mate[lca];
+        self.flower[b] = vec![lca];
+        for (start, mirrored) in [(u, true), (v, false)] {
+            let mut x = start;
+            while x != lca {
+                let y = self.st[self.mate[x]];
+                self.flower[b].extend([x, y]);
+                self.push(y);
+                x = self.st[self.pa[y]];
+            }
+            if mirrored {
+                self.flower[b][1..].reverse();
+            }
+        }
+        self.set_st(b, b);
+        for x in 1..=self.n_x {
+            self.g[b][x].w = 0;
+            self.g[x][b].w = 0;
+        }
+        self.flower_from[b].fill(0);
+        for i in 0..self.flower[b].len() {
+            let xs = self.flower[b][i];
+            for x in 1..=self.n_x {
+                if self.g[b][x].w == 0 || self.slack_of(self.g[xs][x]) < self.slack_of(self.g[b][x])
+                {
+                    self.g[b][x] = self.g[xs][x];
+                    self.g[x][b] = self.g[x][xs];
+                }
+            }
+            for x in 1..=self.n {
+                if self.flower_from[xs][x] != 0 {
+                    self.flower_from[b][x] = xs;
+                }
+            }
+        }
+        self.set_slack(b);
+    }
+
+    fn expand_blossom(&mut self, b: usize) {
+        for i in 0..self.flower[b].len() {
+            let x = self.flower[b][i];
+            self.set_st(x, x);
+        }
+        let xr = self.flower_from[b][self.g[b][self.pa[b]].u as usize];
+        let pr = self.even_position(b, xr);
+        for i in (0..pr).step_by(2) {
+            let (xs, xns) = (self.flower[b][i], self.flower[b][i + 1]);
+            self.pa[xs] = self.g[xns][xs].u as usize;
+            self.label[xs] = 1;
+            self.label[xns] = 0;
+            self.slack[xs] = 0;
+            self.set_slack(xns);
+            self.push(xns);
+        }
+        self.label[xr] = 1;
+        self.pa[xr] = self.pa[b];
+        for i in pr + 1..self.flower[b].len() {
+            let xs = self.flower[b][i];
+            self.label[xs] = -1;
+            self.set_slack(xs);
+        }
+        self.st[b] = 0;
+    }
+
+    /// Follows a tight edge; `true` once it completes an augmenting path.
+    fn on_found_edge(&mut self, e: Edge) -> bool {
+        let (u, v) = (self.st[e.u as usize], self.st[e.v as usize]);
+        if self.label[v] == -1 {
+            self.pa[v] = e.u as usize;
+            self.label[v] = 1;
+            let nu = self.st[self.mate[v]];
+            self.slack[v] = 0;
+            self.slack[nu] = 0;
+            self.label[nu] = 0;
+            self.push(nu);
+        } else if self.label[v] == 0 {
+            let lca = self.lowest_common_ancestor(u, v);
+            if lca == 0 {
+                self.augment(u, v);
+                self.augment(v, u);
+                return true;
+            }
+            self.add_blossom(u, lca, v);
+        }
+        false
+    }
+
+    /// One search phase; `false` once no augmentation adds weight.
+    fn augment_once(&mut self) -> bool {
+        let (n, n_x) = (self.n, self.n_x);
+        self.label[1..=n_x].fill(-1);
+        self.slack[1..=n_x].fill(0);
+        self.queue.clear();
+        for x in 1..=n_x {
+            if self.st[x] == x && self.mate[x] == 0 {
+                self.pa[x] = 0;
+                self.label[x] = 0;
+                self.push(x);
+            }
+        }
+        if self.queue.is_empty() {
+            return false;
+        }
+        loop {
+            while let Some(u) = self.queue.pop_front() {
+                if self.label[self.st[u]] == 1 {
+                    continue;
+                }
+                for v in 1..=n {
+                    let e = self.g[u][v];
+                    if e.w > 0 && self.st[u] != self.st[v] {
+                        if self.slack_of(e) == 0 {
+                            if self.on_found_edge(e) {
+                                return true;
+                            }
+                        } else {
+                            self.update_slack(u, self.st[v]);
+                        }
+                    }
+                }
+            }
+            let mut delta = i64::MAX;
+            for b in n + 1..=self.n_x {
+                if self.st[b] == b && self.label[b] == 1 {
+                    delta = delta.min(self.lab[b] / 2);
+                }
+            }
+            for x in 1..=self.n_x {
+                if self.st[x] == x && self.slack[x] != 0 {
+                    let slack = self.slack_of(self.g[self.slack[x]][x]);
+                    match self.label[x] {
+                        -1 => delta = delta.min(slack),
+                        0 => delta = delta.min(slack / 2),
+                        _ => {}
+                    }
+                }
+            }
+            for u in 1..=n {
+                match self.label[self.st[u]] {
+                    0 => {
+                        if self.lab[u] <= delta {
+                            return false;
+                        }
+                        self.lab[u] -= delta;
+                    }
+                    1 => self.lab[u] += delta,
+                    _ => {}
+                }
+            }
+            for b in n + 1..=self.n_x {
+                if self.st[b] == b {
+                    match self.label[b] {
+                        0 => self.lab[b] += 2 * delta,
+                        1 => self.lab[b] -= 2 * delta,
+                        _ => {}
+                    }
+                }
+            }
+            self.queue.clear();
+            for x in 1..=self.n_x {
+                let s = self.slack[x];
+                if self.st[x] == x
+                    && s != 0
+                    && self.st[s] != x
+                    && self.slack_of(self.g[s][x]) == 0
+                    && self.on_found_edge(self.g[s][x])
+                {
+                    return true;
+                }
+            }
+            for b in n + 1..=self.n_x {
+                if self.st[b] == b && self.label[b] == 1 && self.lab[b] == 0 {
+                    self.expand_blossom(b);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{instance_to_matrix, permutation_weight, Instance};
+
+    fn weight(pairs: &[(usize, usize)], adj_matrix: &[Vec<usize>]) -> usize {
+        pairs.iter().map(|&(u, v)| adj_matrix[u][v]).sum()
+    }
+
+    /// Lightest perfect matching by dynamic programming over subsets.
+    fn brute_force(adj_matrix: &[Vec<usize>]) -> usize {
+        let count = adj_matrix.len();
+        let mut best = vec![usize::MAX; 1 << count];
+        best[0] = 0;
+        for mask in 0..1_usize << count {
+            if best[mask] == usize::MAX || mask.count_ones() as usize == count {
+                continue;
+            }
+            let i = (!mask).trailing_zeros() as usize;
+            for (j, &d) in adj_matrix[i].iter().enumerate().skip(i + 1) {
+                if mask >> j & 1 == 0 {
+                    let next = mask | 1 << i | 1 << j;
+                    best[next] = best[next].min(best[mask] + d);
+                }
+            }
+        }
+        best[(1 << count) - 1]
+    }
+
+    #[test]
+    fn blossom_matches_brute_force() {
+        // Small LCG so the test needs no rand.
+        let mut state = 12345_u64;
+        let mut random = |bound: usize| {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (state >> 33) as usize % bound
+        };
+        for trial in 0..200 {
+            let count = 2 * (1 + trial % 6);
+            let mut adj_matrix = vec![vec![0; count]; count];
+            for (i, j) in (0..count).flat_map(|i| (i + 1..count).map(move |j| (i, j))) {
+                adj_matrix[i][j] = 1 + random(if trial % 2 == 0 { 10 } else { 1000 });
+                adj_matrix[j][i] = adj_matrix[i][j];
+            }
+            let cities: Vec<usize> = (0..count).collect();
+            let exact = blossom_matching(&adj_matrix, &cities);
+            let greedy = greedy_matching(&adj_matrix, &cities);
+            for pairs in [&exact, &greedy] {
+                let mut covered: Vec<usize> = pairs.iter().flat_map(|&(u, v)| [u, v]).collect();
+                covered.sort_unstable();
+                assert!(covered.into_iter().eq(0..count));
+            }
+            let optimum = brute_force(&adj_matrix);
+            assert_eq!(weight(&exact, &adj_matrix), optimum, "trial {trial}");
+            assert!(weight(&greedy, &adj_matrix) >= optimum);
+        }
+    }
+
+    #[test]
+    fn christofides_stays_within_one_and_a_half_of_optimum() {
+        // Instances with their optimal tour lengths.
+        for (path, optimum) in [
+            ("../l2/test_data/1.tsp", 564),
+            ("../l2/test_data/2.tsp", 1019),
+        ] {
+            let instance = Instance::from_file(path).unwrap();
+            let adj_matrix = instance_to_matrix(&instance);
+            let tour = christofides(&adj_matrix);
+            let mut sorted = tour.clone();
+            sorted.sort_unstable();
+            assert!(sorted.into_iter().eq(0..instance.dimension));
+            let weight = permutation_weight(&tour, &adj_matrix);
+            assert!(2 * weight <= 3 * optimum, "{path}: {weight}");
+        }
+    }
+}
@@ -0,0 +1,468 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use crate::tsplib::Instance;
+
+/// Weight of the edge from city `i` to city `j`, whatever stores it. Every
+/// search takes one of these rather than a `DistanceMatrix`, so instances too
+/// large for n² words can still be solved.
+pub trait Distance {
+    fn dist(&self, i: usize, j: usize) -> usize;
+    fn point_count(&self) -> usize;
+
+    /// Whether `dist(i, j) == dist(j, i)` for every pair. Backends that know
+    /// the answer up front should override the O(n²) check.
+    fn symmetric(&self) -> bool {
+        (0..self.point_count()).all(|i| (0..i).all(|j| self.dist(i, j) == self.dist(j, i)))
+    }
+}
+
+impl Distance for [Vec<usize>] {
+    fn dist(&self, i: usize, j: usize) -> usize {
+        self[i][j]
+    }
+
+    fn point_count(&self) -> usize {
+        self.len()
+    }
+}
+
+impl Distance for Vec<Vec<usize>> {
+    fn dist(&self, i: usize, j: usize) -> usize {
+        self[i][j]
+    }
+
+    fn point_count(&self) -> usize {
+        self.len()
+    }
+}
+
+impl<D: Distance + ?Sized> Distance for &D {
+    fn dist(&self, i: usize, j: usize) -> usize {
+        (**self).dist(i, j)
+    }
+
+    fn point_count(&self) -> usize {
+        (**self).point_count()
+    }
+
+    fn symmetric(&self) -> bool {
+        (**self).symmetric()
+    }
+}
+
+/// Unsigned integer a `DenseMatrix` stores its entries as.
+pub trait Cell: Copy {
+    const MAX: usize;
+    fn from_usize(d: usize) -> Self;
+    fn to_usize(self) -> usize;
+}
+
+macro_rules! cell {
+    ($($t:ty),*) => {$(
+        impl Cell for $t {
+            const MAX: usize = <$t>::MAX as usize;
+
+            fn from_usize(d: usize) -> Self {
+                d as $t
+            }
+
+            fn to_usize(self) -> usize {
+                self as usize
+            }
+        }
+    )*};
+}
+
+cell!(u16, u32, u64, usize);
+
+/// Row-major n×n matrix in one allocation, entries stored as `T`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DenseMatrix<T = usize> {
+    point_count: usize,
+    data: Vec<T>,
+}
+
+impl<T: Cell> DenseMatrix<T> {
+    /// Panics if a distance does not fit in `T`; `FlatMatrix` picks a `T`
+    /// that does.
+    pub fn from_instance(instance: &Instance) -> Self {
+        Self::try_from_fn(instance.dimension, |i, j| instance.distance(i, j))
+            .expect("distance does not fit the matrix type")
+    }
+
+    /// Panics if a distance does not fit in `T`.
+    pub fn from_rows(adj_matrix: &[Vec<usize>]) -> Self {
+        Self::try_from_fn(adj_matrix.len(), |i, j| adj_matrix[i][j])
+            .expect("distance does not fit the matrix type")
+    }
+
+    /// `None` as soon as a distance does not fit in `T`. The diagonal is
+    /// zero whatever `distance` says.
+    fn try_from_fn(point_count: usize, distance: impl Fn(usize, usize) -> usize) -> Option<Self> {
+        let mut data = Vec::with_capacity(point_count * point_count);
+        for i in 0..point_count {
+            for j in 0..point_count {
+                let d = if i == j { 0 } else { distance(i, j) };
+                if d > T::MAX {
+                    return None;
+                }
+                data.push(T::from_usize(d));
+            }
+        }
+        Some(DenseMatrix { point_count, data })
+    }
+}
+
+impl<T: Cell> Distance for DenseMatrix<T> {
+    fn dist(&self, i: usize, j: usize) -> usize {
+        self.data[i * self.point_count + j].to_usize()
+    }
+
+    fn point_count(&self) -> usize {
+        self.point_count
+    }
+}
+
+/// `DenseMatrix` with the narrowest entries that hold the largest distance:
+/// 2 bytes per entry on most TSPLIB instances instead of 8, so a 1000-city
+/// matrix takes 2 MB and stays in cache far longer than
+/// `instance_to_matrix`'s rows of `usize`. Its own `dist` matches on the
+/// width every call; hot loops should get at the matrix inside with
+/// `with_flat_matrix!` instead.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FlatMatrix {
+    U16(DenseMatrix<u16>),
+    U32(DenseMatrix<u32>),
+    U64(DenseMatrix<u64>),
+}
+
+/// Evaluates `$body` with `$matrix` bound to the `DenseMatrix` inside a
+/// `&FlatMatrix`, so generic code is compiled once per width and never
+/// branches on it:
+///
+/// ```
+/// # use tsp_core::{permutation_weight, with_flat_matrix, FlatMatrix};
+/// let flat = FlatMatrix::from_rows(&[vec![0, 2, 5], vec![2, 0, 3], vec![5, 3, 0]]);
+/// assert_eq!(with_flat_matrix!(&flat, matrix => permutation_weight(&[0, 1, 2], matrix)), 10);
+/// ```
+#[macro_export]
+macro_rules! with_flat_matrix {
+    ($flat:expr, $matrix:ident => $body:expr) => {
+        match $flat {
+            $crate::FlatMatrix::U16($matrix) => $body,
+            $crate::FlatMatrix::U32($matrix) => $body,
+            $crate::FlatMatrix::U64($matrix) => $body,
+        }
+    };
+}
+
+impl FlatMatrix {
+    pub fn from_instance(instance: &Instance) -> Self {
+        Self::from_fn(instance.dimension, |i, j| instance.distance(i, j))
+    }
+
+    pub fn from_rows(adj_matrix: &[Vec<usize>]) -> Self {
+        Self::from_fn(adj_matrix.len(), |i, j| adj_matrix[i][j])
+    }
+
+    // Tries each width in turn; building stops at the first distance that
+    // does not fit, so usually there is only one pass.
+    fn from_fn(point_count: usize, distance: impl Fn(usize, usize) -> usize) -> Self {
+        if let Some(matrix) = DenseMatrix::try_from_fn(point_count, &distance) {
+            return FlatMatrix::U16(matrix);
+        }
+        if let Some(matrix) = DenseMatrix::try_from_fn(point_count, &distance) {
+            return FlatMatrix::U32(matrix);
+        }
+        FlatMatrix::U64(DenseMatrix::try_from_fn(point_count, &distance).unwrap())
+    }
+
+    /// Bytes per entry.
+    pub fn width(&self) -> usize {
+        match self {
+            FlatMatrix::U16(_) => 2,
+            FlatMatrix::U32(_) => 4,
+            FlatMatrix::U64(_) => 8,
+        }
+    }
+}
+
+impl Distance for FlatMatrix {
+    // Not generic, so it needs the hint to be inlined into the searches.
+    #[inline]
+    fn dist(&self, i: usize, j: usize) -> usize {
+        match self {
+            FlatMatrix::U16(matrix) => matrix.dist(i, j),
+            FlatMatrix::U32(matrix) => matrix.dist(i, j),
+            FlatMatrix::U64(matrix) => matrix.dist(i, j),
+        }
+    }
+
+    fn point_count(&self) -> usize {
+        match self {
+            FlatMatrix::U16(matrix) => matrix.point_count,
+            FlatMatrix::U32(matrix) => matrix.point_count,
+            FlatMatrix::U64(matrix) => matrix.point_count,
+        }
+    }
+}
+
+/// Upper triangle of a symmetric matrix without the diagonal, row after row:
+/// n(n-1)/2 entries, half of a `DenseMatrix`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PackedMatrix {
+    point_count: usize,
+    data: Vec<usize>,
+}
+
+impl PackedMatrix {
+    /// `None` for an asymmetric instance.
+    pub fn from_instance(instance: &Instance) -> Option<Self> {
+        if instance
+            .weights
+            .as_ref()
+            .is_some_and(|weights| !weights.symmetric())
+        {
+            return None;
+        }
+        let point_count = instance.dimension;
+        let mut data = Vec::with_capacity(point_count * point_count.saturating_sub(1) / 2);
+        for i in 0..point_count {
+            data.extend((i + 1..point_count).map(|j| instance.distance(i, j)));
+        }
+        Some(PackedMatrix { point_count, data })
+    }
+}
+
+impl Distance for PackedMatrix {
+    fn dist(&self, i: usize, j: usize) -> usize {
+        let (i, j) = if i < j {
+            (i, j)
+        } else if i > j {
+            (j, i)
+        } else {
+            return 0;
+        };
+        // Rows 0..i hold n-1, n-2, .., n-i entries.
+        self.data[i * (2 * self.point_count - i - 1) / 2 + j - i - 1]
+    }
+
+    fn point_count(&self) -> usize {
+        self.point_count
+    }
+
+    fn symmetric(&self) -> bool {
+        true
+    }
+}
+
+/// Computes every distance from the instance on demand, O(n) memory.
+/// `with_cache` keeps the most recently used pairs in an LRU cache, which
+/// pays off for weight types that need trigonometry, such as `GEO`.
+pub struct CoordinateDistance<'a> {
+    instance: &'a Instance,
+    cache: Option<Mutex<Lru>>,
+}
+
+impl<'a> CoordinateDistance<'a> {
+    pub fn new(instance: &'a Instance) -> Self {
+        CoordinateDistance {
+            instance,
+            cache: None,
+        }
+    }
+
+    pub fn with_cache(instance: &'a Instance, capacity: usize) -> Self {
+        CoordinateDistance {
+            instance,
+            cache: (capacity > 0).then(|| Mutex::new(Lru::new(capacity))),
+        }
+    }
+}
+
+impl Distance for CoordinateDistance<'_> {
+    fn dist(&self, i: usize, j: usize) -> usize {
+        if i == j {
+            return 0;
+        }
+        let Some(cache) = &self.cache else {
+            return self.instance.distance(i, j);
+        };
+        let key = i * self.instance.dimension + j;
+        let mut cache = cache.lock().unwrap();
+        if let Some(d) = cache.get(key) {
+            return d;
+        }
+        let d = self.instance.distance(i, j);
+        cache.insert(key, d);
+        d
+    }
+
+    fn point_count(&self) -> usize {
+        self.instance.dimension
+    }
+
+    fn symmetric(&self) -> bool {
+        self.instance
+            .weights
+            .as_ref()
+            .is_none_or(|weights| weights.symmetric())
+    }
+}
+
+const NIL: usize = usize::MAX;
+
+#[derive(Clone, Debug)]
+struct Slot {
+    key: usize,
+    value: usize,
+    prev: usize,
+    next: usize,
+}
+
+/// Fixed-capacity map that evicts the least recently used key. Slots form a
+/// doubly-linked list from `head`, the most recent, to `tail`.
+#[derive(Clone, Debug)]
+struct Lru {
+    capacity: usize,
+    map: HashMap<usize, usize>,
+    slots: Vec<Slot>,
+    head: usize,
+    tail: usize,
+}
+
+impl Lru {
+    fn new(capacity: usize) -> Self {
+        Lru {
+            capacity,
+            map: HashMap::with_capacity(capacity),
+            slots: Vec::with_capacity(capacity),
+            head: NIL,
+            tail: NIL,
+        }
+    }
+
+    fn get(&mut self, key: usize) -> Option<usize> {
+        let slot = *self.map.get(&key)?;
+        self.unlink(slot);
+        self.push_front(slot);
+        Some(self.slots[slot].value)
+    }
+
+    fn insert(&mut self, key: usize, value: usize) {
+        let slot = if self.slots.len() < self.capacity {
+            self.slots.push(Slot {
+                key,
+                value,
+                prev: NIL,
+                next: NIL,
+            });
+            self.slots.len() - 1
+        } else {
+            let slot = self.tail;
+            self.unlink(slot);
+            self.map.remove(&self.slots[slot].key);
+            self.slots[slot].key = key;
+            self.slots[slot].value = value;
+            slot
+        };
+        self.map.insert(key, slot);
+        self.push_front(slot);
+    }
+
+    fn unlink(&mut self, slot: usize) {
+        let Slot { prev, next, .. } = self.slots[slot];
+        if prev == NIL {
+            self.head = next;
+        } else {
+            self.slots[prev].next = next;
+        }
+        if next == NIL {
+            self.tail = prev;
+        } else {
+            self.slots[next].prev = prev;
+        }
+    }
+
+    fn push_front(&mut self, slot: usize) {
+        self.slots[slot].prev = NIL;
+        self.slots[slot].next = self.head;
+        if self.head == NIL {
+            self.tail = slot;
+        } else {
+            self.slots[self.head].prev = slot;
+        }
+        self.head = slot;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instance_to_matrix;
+
+    fn assert_same(expected: &[Vec<usize>], distance: &impl Distance) {
+        assert_eq!(distance.point_count(), expected.len());
+        for (i, row) in expected.iter().enumerate() {
+            for (j, &d) in row.iter().enumerate() {
+                assert_eq!(distance.dist(i, j), d, "{i} {j}");
+            }
+        }
+    }
+
+    #[test]
+    fn backends_agree_with_the_matrix() {
+        let instance = Instance::from_file("../l2/test_data/1.tsp").unwrap();
+        let adj_matrix = instance_to_matrix(&instance);
+        assert_same(&adj_matrix, &DenseMatrix::<usize>::from_instance(&instance));
+        assert_same(&adj_matrix, &DenseMatrix::<u16>::from_rows(&adj_matrix));
+        assert_same(&adj_matrix, &FlatMatrix::from_instance(&instance));
+        assert_same(
+            &adj_matrix,
+            &PackedMatrix::from_instance(&instance).unwrap(),
+        );
+        assert_same(&adj_matrix, &CoordinateDistance::new(&instance));
+        // A cache much smaller than the matrix has to evict on every row.
+        let cached = CoordinateDistance::with_cache(&instance, 50);
+        assert_same(&adj_matrix, &cached);
+        assert_same(&adj_matrix, &cached);
+        assert!(cached.symmetric() && adj_matrix.symmetric());
+    }
+
+    #[test]
+    fn packed_matrix_needs_a_symmetric_instance() {
+        let data = "NAME : br3\nTYPE : ATSP\nDIMENSION : 3\nEDGE_WEIGHT_TYPE : EXPLICIT\n\
+                    EDGE_WEIGHT_FORMAT : FULL_MATRIX\nEDGE_WEIGHT_SECTION\n\
+                    9999 3 5\n4 9999 2\n8 1 9999\nEOF\n";
+        let instance = Instance::parse(data.as_bytes()).unwrap();
+        assert!(PackedMatrix::from_instance(&instance).is_none());
+        let adj_matrix = instance_to_matrix(&instance);
+        assert!(!CoordinateDistance::new(&instance).symmetric());
+        assert_same(&adj_matrix, &CoordinateDistance::with_cache(&instance, 2));
+    }
+
+    #[test]
+    fn flat_matrix_picks_the_narrowest_width() {
+        let instance = Instance::from_file("../l2/test_data/1.tsp").unwrap();
+        assert_eq!(FlatMatrix::from_instance(&instance).width(), 2);
+        for (far, width) in [(65_535, 2), (65_536, 4), (1 << 32, 8)] {
+            let adj_matrix = vec![vec![0, 1, far], vec![1, 0, 2], vec![far, 2, 0]];
+            let flat = FlatMatrix::from_rows(&adj_matrix);
+            assert_eq!(flat.width(), width);
+            assert_same(&adj_matrix, &flat);
+        }
+        assert!(DenseMatrix::<u16>::try_from_fn(2, |_, _| 65_536).is_none());
+    }
+
+    #[test]
+    fn lru_evicts_the_least_recently_used_key() {
+        let mut lru = Lru::new(2);
+        lru.insert(1, 10);
+        lru.insert(2, 20);
+        assert_eq!(lru.get(1), Some(10));
+        lru.insert(3, 30);
+        assert_eq!(lru.get(2), None);
+        assert_eq!((lru.get(1), lru.get(3)), (Some(10), Some(30)));
+    }
+}
@@ -0,0 +1,193 @@
+use crate::candidates::nearest_neighbours;
+use crate::distance::Distance;
+use crate::mst::{candidate_prim, prim};
+use crate::permutation_weight;
+
+/// Node penalties move in hundredths of a distance unit.
+const SCALE: i64 = 100;
+/// Failed iterations in a row before the step size is halved.
+const PATIENCE: usize = 100;
+/// Nearest neighbours the ascent builds its 1-trees from.
+const CANDIDATES: usize = 10;
+/// Enough 1-trees for the ascent to level off on the lab instances.
+pub const HELD_KARP_ITERATIONS: usize = 3000;
+
+/// Held–Karp lower bound on the length of any tour: the heaviest minimum
+/// 1-tree found by subgradient ascent on node penalties π, for at most
+/// `iterations` 1-trees. The ascent works on the 10-nearest-neighbour graph,
+/// so only the final 1-tree at the best π costs O(n²) and keeps the bound
+/// valid. `upper_bound`, usually the best tour known, sets the step size.
+/// Asymmetric matrices are bounded through `min(d(i, j), d(j, i))`, which no
+/// tour can beat either.
+pub fn held_karp_bound<D: Distance + ?Sized>(
+    adj_matrix: &D,
+    upper_bound: usize,
+    iterations: usize,
+) -> usize {
+    let point_count = adj_matrix.point_count();
+    if point_count < 3 {
+        let tour: Vec<usize> = (0..point_count).collect();
+        return if tour.is_empty() {
+            0
+        } else {
+            permutation_weight(&tour, adj_matrix)
+        };
+    }
+    let mut costs = Penalized {
+        adj_matrix,
+        pi: vec![0; point_count],
+        offset: 0,
+        symmetric: adj_matrix.symmetric(),
+    };
+    // Neighbour lists of cities 1..n, renumbered like the tree's.
+    let candidates: Vec<Vec<usize>> = nearest_neighbours(adj_matrix, CANDIDATES)[1..]
+        .iter()
+        .map(|list| list.iter().filter(|&&j| j != 0).map(|&j| j - 1).collect())
+        .collect();
+    let mut best_pi = costs.pi.clone();
+    let target = upper_bound as i64 * SCALE;
+    let mut best = i64::MIN;
+    let mut lambda = 2.0;
+    let mut stale = 0;
+    for _ in 0..iterations {
+        let (bound, degree) = costs.one_tree(Some(&candidates));
+        if bound > best {
+            best = bound;
+            best_pi.clone_from(&costs.pi);
+            stale = 0;
+        } else {
+            stale += 1;
+            if stale == PATIENCE {
+                lambda /= 2.0;
+                stale = 0;
+            }
+        }
+        // A 1-tree with every degree 2 is a tour, and so optimal.
+        let norm: i64 = degree.iter().map(|&d| (d - 2) * (d - 2)).sum();
+        let step = lambda * (target - bound) as f64 / norm.max(1) as f64;
+        if norm == 0 || step < 1.0 {
+            break;
+        }
+        for (pi, &d) in costs.pi.iter_mut().zip(&degree) {
+            *pi += (step * (d - 2) as f64).round() as i64;
+        }
+        costs.offset = -2 * costs.pi.iter().copied().min().unwrap().min(0);
+    }
+    // The sparse tree may miss cheaper edges; π = 0 still gives the plain
+    // 1-tree should the penalties overshoot.
+    let (plain, _) = Penalized {
+        pi: vec![0; point_count],
+        offset: 0,
+        ..costs
+    }
+    .one_tree(None);
+    costs.pi = best_pi;
+    costs.offset = -2 * costs.pi.iter().copied().min().unwrap().min(0);
+    let best = costs.one_tree(None).0.max(plain);
+    // Tours have integer lengths, so the bound rounds up.
+    (best.max(0) + SCALE - 1) as usize / SCALE as usize
+}
+
+/// `SCALE * d(i, j) + π_i + π_j` over cities `1..n`, shifted by `offset` so no
+/// cost is negative: the same constant on every edge leaves Prim's tree alone.
+struct Penalized<'a, D: ?Sized> {
+    adj_matrix: &'a D,
+    pi: Vec<i64>,
+    offset: i64,
+    symmetric: bool,
+}
+
+impl<D: Distance + ?Sized> Penalized<'_, D> {
+    fn cost(&self, i: usize, j: usize) -> i64 {
+        let d = if self.symmetric {
+            self.adj_matrix.dist(i, j)
+        } else {
+            self.adj_matrix.dist(i, j).min(self.adj_matrix.dist(j, i))
+        };
+        SCALE * d as i64 + self.pi[i] + self.pi[j]
+    }
+
+    /// Weight of the minimum 1-tree minus 2Σπ, in `SCALE` units, and the
+    /// degree of every city in it. City 0 is the one outside the tree.
+    /// `candidates` restricts the tree to those edges, numbered from city 1.
+    fn one_tree(&self, candidates: Option<&[Vec<usize>]>) -> (i64, Vec<i64>) {
+        let point_count = self.pi.len();
+        let parent = match candidates {
+            Some(candidates) => candidate_prim(self, candidates),
+            None => prim(self, point_count - 1),
+        };
+        let mut degree = vec![0; point_count];
+        let mut weight = 0;
+        for (v, &u) in parent.iter().enumerate().skip(1) {
+            weight += self.cost(v + 1, u + 1);
+            degree[v + 1] += 1;
+            degree[u + 1] += 1;
+        }
+        let mut nearest: Vec<usize> = (1..point_count).collect();
+        nearest.select_nth_unstable_by_key(1, |&j| self.cost(0, j));
+        for &j in &nearest[..2] {
+            weight += self.cost(0, j);
+            degree[j] += 1;
+        }
+        degree[0] = 2;
+        (weight - 2 * self.pi.iter().sum::<i64>(), degree)
+    }
+}
+
+impl<D: Distance + ?Sized> Distance for Penalized<'_, D> {
+    fn dist(&self, i: usize, j: usize) -> usize {
+        (self.cost(i + 1, j + 1) + self.offset) as usize
+    }
+
+    fn point_count(&self) -> usize {
+        self.pi.len() - 1
+    }
+
+    fn symmetric(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mst::mst_weight;
+    use crate::{instance_to_matrix, Instance};
+
+    #[test]
+    fn bound_lies_between_mst_and_optimum() {
+        // Instances with their optimal tour lengths.
+        for (path, optimum) in [
+            ("../l2/test_data/1.tsp", 564),
+            ("../l2/test_data/2.tsp", 1019),
+        ] {
+            let instance = Instance::from_file(path).unwrap();
+            let adj_matrix = instance_to_matrix(&instance);
+            let mst = mst_weight(&prim(&adj_matrix, instance.dimension), &adj_matrix);
+            let bound = held_karp_bound(&adj_matrix, optimum * 110 / 100, HELD_KARP_ITERATIONS);
+            assert!(mst < bound && bound <= optimum, "{path}: {mst} {bound}");
+            // These VLSI instances keep a Held–Karp gap of about 1.5%.
+            assert!(bound * 102 / 100 >= optimum, "{path}: {bound}");
+        }
+    }
+
+    #[test]
+    fn tiny_and_asymmetric_matrices() {
+        let square = vec![
+            vec![0, 1, 2, 1],
+            vec![1, 0, 1, 2],
+            vec![2, 1, 0, 1],
+            vec![1, 2, 1, 0],
+        ];
+        assert_eq!(held_karp_bound(&square, 4, 100), 4);
+        assert_eq!(held_karp_bound(&vec![vec![0, 3], vec![5, 0]], 8, 100), 8);
+        let asymmetric = vec![
+            vec![0, 1, 9, 9, 9],
+            vec![9, 0, 1, 9, 9],
+            vec![9, 9, 0, 1, 9],
+            vec![9, 9, 9, 0, 1],
+            vec![1, 9, 9, 9, 0],
+        ];
+        assert!(held_karp_bound(&asymmetric, 5, 100) <= 5);
+    }
+}
@@ -0,0 +1,22 @@
+pub mod candidates;
+pub mod christofides;
+pub mod distance;
+pub mod held_karp;
+pub mod lk;
+pub mod matrix;
+pub mod moves;
+pub mod mst;
+pub mod order;
+pub mod tour;
+pub mod tsplib;
+
+pub use candidates::{candidate_lists, nearest_neighbours, KdTree};
+pub use christofides::{christofides, min_weight_matching, BLOSSOM_LIMIT};
+pub use distance::{Cell, CoordinateDistance, DenseMatrix, Distance, FlatMatrix, PackedMatrix};
+pub use held_karp::{held_karp_bound, HELD_KARP_ITERATIONS};
+pub use lk::LinKernighan;
+pub use matrix::{instance_to_matrix, is_symmetric, DistanceMatrix};
+pub use mst::{candidate_kruskal, candidate_prim, MST_CANDIDATES};
+pub use order::{ArrayTour, TourOrder, TwoLevelList, TWO_LEVEL_THRESHOLD};
+pub use tour::{permutation_weight, Tour, TourError};
+pub use tsplib::{read_tour, write_tour, Instance, ParseError};
@@ -0,0 +1,269 @@
+use std::collections::VecDeque;
+
+use crate::candidates::nearest_neighbours;
+use crate::distance::Distance;
+use crate::order::{ArrayTour, TourOrder, TwoLevelList, TWO_LEVEL_THRESHOLD};
+use crate::{is_symmetric, permutation_weight};
+
+/// How many candidates the first and second level of a chain try before
+/// giving up; deeper levels only follow the most promising one.
+const BREADTH: [usize; 2] = [5, 3];
+
+/// Lin–Kernighan style variable-depth search. A chain keeps `t1` fixed and
+/// repeatedly replaces the edge at the open end by one to a nearby city,
+/// which is a 2-opt move; it stops at the first closed tour that beats every
+/// shorter prefix, or after `max_depth` moves. Symmetric matrices only.
+///
+/// One descent ends within about 5% of optimal (3–4% on xql662 and xit1083).
+/// Tours within 2% need it chained: l2 kicks the tour with a double bridge
+/// and re-runs `improve` in its iterated local search.
+#[derive(Clone, Debug)]
+pub struct LinKernighan {
+    candidates: Vec<Vec<usize>>,
+    pub max_depth: usize,
+    symmetric: bool,
+}
+
+impl LinKernighan {
+    /// Candidate lists of the `neighbours` nearest cities.
+    pub fn new<D: Distance + ?Sized>(adj_matrix: &D, neighbours: usize) -> Self {
+        Self::with_candidates(adj_matrix, nearest_neighbours(adj_matrix, neighbours))
+    }
+
+    /// Candidate lists computed elsewhere, e.g. by `candidate_lists`; each
+    /// one sorted closest first.
+    pub fn with_candidates<D: Distance + ?Sized>(
+        adj_matrix: &D,
+        candidates: Vec<Vec<usize>>,
+    ) -> Self {
+        LinKernighan {
+            candidates,
+            max_depth: 50,
+            symmetric: is_symmetric(adj_matrix),
+        }
+    }
+
+    /// Runs chains from every city until none of them improves `tour`.
+    /// Returns the tour, the number of improving chains and its weight, like
+    /// l2's `local_search`. Asymmetric instances come back unchanged with 0
+    /// steps: as the improvement step of an ILS or the GA, LK does nothing on
+    /// an ATSP, so use l2's `atsp_local_search` there instead.
+    pub fn improve<D: Distance + ?Sized>(
+        &self,
+        tour: Vec<usize>,
+        adj_matrix: &D,
+    ) -> (Vec<usize>, usize, usize) {
+        let length = tour.len();
+        if length < 5 || !self.symmetric {
+            let weight = permutation_weight(&tour, adj_matrix);
+            return (tour, 0, weight);
+        }
+        let (tour, steps) = if length > TWO_LEVEL_THRESHOLD {
+            self.run(TwoLevelList::new(&tour), adj_matrix)
+        } else {
+            self.run(ArrayTour::new(&tour), adj_matrix)
+        };
+        let weight = permutation_weight(&tour, adj_matrix);
+        (tour, steps, weight)
+    }
+
+    fn run<T: TourOrder, D: Distance + ?Sized>(
+        &self,
+        tour: T,
+        adj_matrix: &D,
+    ) -> (Vec<usize>, usize) {
+        let order = tour.order();
+        let mut queue: VecDeque<usize> = order.iter().copied().collect();
+        let mut queued = vec![true; order.len()];
+        let mut chain = Chain {
+            adj_matrix,
+            candidates: &self.candidates,
+            max_depth: self.max_depth,
+            tour,
+            forward: true,
+            added: Vec::new(),
+            touched: Vec::new(),
+        };
+        let mut steps = 0;
+        while let Some(t1) = queue.pop_front() {
+            queued[t1] = false;
+            // Try both tour neighbours of `t1` as `t2`.
+            for forward in [true, false] {
+                chain.forward = forward;
+                let t2 = if forward {
+                    chain.tour.next(t1)
+                } else {
+                    chain.tour.prev(t1)
+                };
+                if chain.step(t1, t2, chain.d(t1, t2), 0, 0) {
+                    steps += 1;
+                    chain.touched.push(t1);
+                    for city in chain.touched.drain(..) {
+                        if !queued[city] {
+                            queued[city] = true;
+                            queue.push_back(city);
+                        }
+                    }
+                    chain.added.clear();
+                    break;
+                }
+            }
+        }
+        (chain.tour.order(), steps)
+    }
+}
+
+struct Chain<'a, T, D: ?Sized> {
+    adj_matrix: &'a D,
+    candidates: &'a [Vec<usize>],
+    max_depth: usize,
+    tour: T,
+    /// Whether `t2` follows `t1` in tour order or precedes it; chains work
+    /// on the tour read in that direction.
+    forward: bool,
+    added: Vec<(usize, usize)>,
+    touched: Vec<usize>,
+}
+
+impl<T: TourOrder, D: Distance + ?Sized> Chain<'_, T, D> {
+    fn d(&self, a: usize, b: usize) -> i64 {
+        self.adj_matrix.dist(a, b) as i64
+    }
+
+    fn prev(&self, city: usize) -> usize {
+        if self.forward {
+            self.tour.prev(city)
+        } else {
+            self.tour.next(city)
+        }
+    }
+
+    fn reverse_path(&mut self, from: usize, to: usize) {
+        if self.forward {
+            self.tour.flip(from, to);
+        } else {
+            self.tour.flip(to, from);
+        }
+    }
+
+    /// Extends a chain whose open end is `t2`, which follows `t1`; `gain` is the
+    /// weight removed minus the weight added so far, without the closing
+    /// edge. Returns `true` with the chain applied once closing it saves more
+    /// than `threshold`, and `false` with the tour as it was otherwise.
+    fn step(&mut self, t1: usize, t2: usize, gain: i64, depth: usize, threshold: i64) -> bool {
+        let mut options: Vec<(i64, usize, usize)> = Vec::new();
+        for &t3 in &self.candidates[t2] {
+            let partial = gain - self.d(t2, t3);
+            if partial <= 0 {
+                break;
+            }
+            let t4 = self.prev(t3);
+            if t3 == t1
+                || t4 == t2
+                || self.added.contains(&(t3, t4))
+                || self.added.contains(&(t4, t3))
+            {
+                continue;
+            }
+            options.push((partial + self.d(t3, t4), t3, t4));
+        }
+        options.sort_unstable_by_key(|&(gain, _, _)| std::cmp::Reverse(gain));
+        let breadth = BREADTH.get(depth).copied().unwrap_or(1);
+        for &(gain, t3, t4) in options.iter().take(breadth) {
+            // t1 t2 .. t4 t3 becomes t1 t4 .. t2 t3.
+            self.reverse_path(t2, t4);
+            self.added.push((t2, t3));
+            let closed = gain - self.d(t4, t1);
+            if depth + 1 < self.max_depth
+                && self.step(t1, t4, gain, depth + 1, threshold.max(closed))
+            {
+                self.touched.extend([t2, t3, t4]);
+                return true;
+            }
+            if closed > threshold {
+                self.touched.extend([t2, t3, t4]);
+                return true;
+            }
+            self.added.pop();
+            self.reverse_path(t4, t2);
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{instance_to_matrix, Instance};
+
+    #[test]
+    fn lin_kernighan_reports_true_weight() {
+        let instance = Instance::from_file("../l2/test_data/1.tsp").unwrap();
+        let adj_matrix = instance_to_matrix(&instance);
+        let length = instance.dimension;
+        let lk = LinKernighan::new(&adj_matrix, 8);
+        let start: Vec<usize> = (0..length).map(|k| (k * 37) % length).collect();
+        let (tour, steps, weight) = lk.improve(start.clone(), &adj_matrix);
+        assert!(steps > 0);
+        assert_eq!(weight, permutation_weight(&tour, &adj_matrix));
+        let mut sorted = tour.clone();
+        sorted.sort();
+        assert!(sorted.into_iter().eq(0..length));
+        // xqf131 has an optimal tour of 564.
+        assert!(weight < 564 * 110 / 100, "{weight}");
+        let (_, steps, again) = lk.improve(tour, &adj_matrix);
+        assert_eq!((steps, again), (0, weight));
+    }
+
+    #[test]
+    fn lin_kernighan_on_larger_maps() {
+        use crate::candidate_lists;
+        use crate::mst::{candidate_prim, dfs, parent_to_adj_list};
+        // xql662 and xit1083 with their optimal tour lengths; chained by
+        // l2's iterated local search it gets within 2% (see `chained_lk_test`).
+        for (path, optimum) in [
+            ("../l2/test_data/a.tsp", 2513),
+            ("../l2/test_data/b.tsp", 3558),
+        ] {
+            let instance = Instance::from_file(path).unwrap();
+            let adj_matrix = instance_to_matrix(&instance);
+            let candidates = candidate_lists(&instance, 8);
+            let start = dfs(&parent_to_adj_list(&candidate_prim(
+                &adj_matrix,
+                &candidates,
+            )));
+            let (_, _, weight) =
+                LinKernighan::with_candidates(&adj_matrix, candidates).improve(start, &adj_matrix);
+            assert!(weight <= optimum * 105 / 100, "{path}: {weight}");
+        }
+    }
+
+    #[test]
+    fn asymmetric_tour_is_left_alone() {
+        let adj_matrix = vec![
+            vec![0, 1, 9, 9, 9],
+            vec![9, 0, 1, 9, 9],
+            vec![9, 9, 0, 1, 9],
+            vec![9, 9, 9, 0, 1],
+            vec![1, 9, 9, 9, 0],
+        ];
+        let (tour, steps, weight) =
+            LinKernighan::new(&adj_matrix, 2).improve(vec![0, 2, 1, 3, 4], &adj_matrix);
+        assert_eq!((tour, steps, weight), (vec![0, 2, 1, 3, 4], 0, 29));
+    }
+    #[test]
+    fn two_level_list_gives_the_same_search() {
+        let instance = Instance::from_file("../l2/test_data/1.tsp").unwrap();
+        let adj_matrix = instance_to_matrix(&instance);
+        let length = instance.dimension;
+        let lk = LinKernighan::new(&adj_matrix, 8);
+        let start: Vec<usize> = (0..length).map(|k| (k * 37) % length).collect();
+        let (array, array_steps) = lk.run(ArrayTour::new(&start), &adj_matrix);
+        let (list, list_steps) = lk.run(TwoLevelList::with_group(&start, 4), &adj_matrix);
+        assert_eq!(array_steps, list_steps);
+        assert_eq!(
+            permutation_weight(&array, &adj_matrix),
+            permutation_weight(&list, &adj_matrix)
+        );
+    }
+}
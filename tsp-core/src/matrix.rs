@@ -0,0 +1,20 @@
+use crate::distance::Distance;
+use crate::tsplib::Instance;
+
+/// Row-major distances, `adj_matrix[from][to]`, with zeros on the diagonal.
+pub type DistanceMatrix = Vec<Vec<usize>>;
+
+pub fn instance_to_matrix(instance: &Instance) -> DistanceMatrix {
+    let point_count = instance.dimension;
+    (0..point_count)
+        .map(|i| {
+            (0..point_count)
+                .map(|j| if i == j { 0 } else { instance.distance(i, j) })
+                .collect()
+        })
+        .collect()
+}
+
+pub fn is_symmetric<D: Distance + ?Sized>(adj_matrix: &D) -> bool {
+    adj_matrix.symmetric()
+}
@@ -0,0 +1,295 @@
+use crate::distance::Distance;
+
+/// Weight after inverting `permutation[i..=j]`. On asymmetric matrices every
+/// edge inside the segment changes direction too, which costs O(j - i).
+pub fn invert_weight<D: Distance + ?Sized>(
+    permutation: &[usize],
+    adj_matrix: &D,
+    i: usize,
+    j: usize,
+    weight: usize,
+    symmetric: bool,
+) -> usize {
+    let last = permutation.len() - 1;
+    let pre = i.checked_sub(1).unwrap_or(last);
+    let post = (j + 1) % permutation.len();
+    let mut weight = weight
+        + adj_matrix.dist(permutation[pre], permutation[j])
+        + adj_matrix.dist(permutation[i], permutation[post])
+        - adj_matrix.dist(permutation[pre], permutation[i])
+        - adj_matrix.dist(permutation[j], permutation[post]);
+    if !symmetric {
+        for k in i..j {
+            weight = weight + adj_matrix.dist(permutation[k + 1], permutation[k])
+                - adj_matrix.dist(permutation[k], permutation[k + 1]);
+        }
+    }
+    weight
+}
+
+/// Weight after moving the segment `permutation[i..=j]` between positions `k`
+/// and `k + 1` without inverting it (or-opt / 3-opt segment insertion).
+/// `k` must lie outside `i - 1..=j`.
+pub fn insertion_weight<D: Distance + ?Sized>(
+    permutation: &[usize],
+    adj_matrix: &D,
+    i: usize,
+    j: usize,
+    k: usize,
+    weight: usize,
+) -> usize {
+    let length = permutation.len();
+    let a = permutation[(i + length - 1) % length];
+    let b = permutation[(j + 1) % length];
+    let c = permutation[k];
+    let d = permutation[(k + 1) % length];
+    weight
+        + adj_matrix.dist(a, b)
+        + adj_matrix.dist(c, permutation[i])
+        + adj_matrix.dist(permutation[j], d)
+        - adj_matrix.dist(a, permutation[i])
+        - adj_matrix.dist(permutation[j], b)
+        - adj_matrix.dist(c, d)
+}
+
+/// Like `insertion_weight`, but the segment is put back reversed. On
+/// asymmetric matrices its inner edges change direction too.
+pub fn reversed_insertion_weight<D: Distance + ?Sized>(
+    permutation: &[usize],
+    adj_matrix: &D,
+    i: usize,
+    j: usize,
+    k: usize,
+    weight: usize,
+    symmetric: bool,
+) -> usize {
+    let length = permutation.len();
+    let a = permutation[(i + length - 1) % length];
+    let b = permutation[(j + 1) % length];
+    let c = permutation[k];
+    let d = permutation[(k + 1) % length];
+    let mut weight = weight
+        + adj_matrix.dist(a, b)
+        + adj_matrix.dist(c, permutation[j])
+        + adj_matrix.dist(permutation[i], d)
+        - adj_matrix.dist(a, permutation[i])
+        - adj_matrix.dist(permutation[j], b)
+        - adj_matrix.dist(c, d);
+    if !symmetric {
+        for m in i..j {
+            weight = weight + adj_matrix.dist(permutation[m + 1], permutation[m])
+                - adj_matrix.dist(permutation[m], permutation[m + 1]);
+        }
+    }
+    weight
+}
+
+pub fn insert_segment(permutation: &mut [usize], i: usize, j: usize, k: usize) {
+    let segment = j - i + 1;
+    if k > j {
+        permutation[i..=k].rotate_left(segment);
+    } else {
+        permutation[(k + 1)..=j].rotate_right(segment);
+    }
+}
+
+pub fn insert_segment_reversed(permutation: &mut [usize], i: usize, j: usize, k: usize) {
+    permutation[i..=j].reverse();
+    insert_segment(permutation, i, j, k);
+}
+
+/// Weight after swapping the cities at positions `i` and `j`. Only the (up to
+/// four) edges touching either position are looked at, so neighbouring and
+/// wrapping positions are handled too.
+pub fn swap_weight<D: Distance + ?Sized>(
+    permutation: &[usize],
+    adj_matrix: &D,
+    i: usize,
+    j: usize,
+    weight: usize,
+) -> usize {
+    let length = permutation.len();
+    let mut edges = [(i + length - 1) % length, i, (j + length - 1) % length, j];
+    edges.sort_unstable();
+    let swapped = |p: usize| {
+        let p = p % length;
+        if p == i {
+            permutation[j]
+        } else if p == j {
+            permutation[i]
+        } else {
+            permutation[p]
+        }
+    };
+    let mut weight = weight;
+    for (k, &e) in edges.iter().enumerate() {
+        if k > 0 && edges[k - 1] == e {
+            continue;
+        }
+        weight += adj_matrix.dist(swapped(e), swapped(e + 1));
+    }
+    for (k, &e) in edges.iter().enumerate() {
+        if k > 0 && edges[k - 1] == e {
+            continue;
+        }
+        weight -= adj_matrix.dist(permutation[e], permutation[(e + 1) % length]);
+    }
+    weight
+}
+
+/// A single neighbourhood step on a tour, identified by positions.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Move {
+    /// Exchange the cities at two positions.
+    Swap(usize, usize),
+    /// Reverse `permutation[i..=j]` (2-opt).
+    Reversal(usize, usize),
+    /// Move `permutation[i..=j]` between positions `k` and `k + 1`; a single
+    /// city when `i == j`, or-opt for short segments.
+    Insertion { i: usize, j: usize, k: usize },
+    /// `Insertion` with the segment reversed.
+    ReversedInsertion { i: usize, j: usize, k: usize },
+}
+
+impl Move {
+    /// Tour weight after the move, without touching the permutation.
+    pub fn weight<D: Distance + ?Sized>(
+        &self,
+        permutation: &[usize],
+        adj_matrix: &D,
+        weight: usize,
+        symmetric: bool,
+    ) -> usize {
+        match *self {
+            Move::Swap(i, j) => swap_weight(permutation, adj_matrix, i, j, weight),
+            Move::Reversal(i, j) => invert_weight(permutation, adj_matrix, i, j, weight, symmetric),
+            Move::Insertion { i, j, k } => {
+                insertion_weight(permutation, adj_matrix, i, j, k, weight)
+            }
+            Move::ReversedInsertion { i, j, k } => {
+                reversed_insertion_weight(permutation, adj_matrix, i, j, k, weight, symmetric)
+            }
+        }
+    }
+
+    pub fn apply(&self, permutation: &mut [usize]) {
+        match *self {
+            Move::Swap(i, j) => permutation.swap(i, j),
+            Move::Reversal(i, j) => permutation[i..=j].reverse(),
+            Move::Insertion { i, j, k } => insert_segment(permutation, i, j, k),
+            Move::ReversedInsertion { i, j, k } => insert_segment_reversed(permutation, i, j, k),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cycle_weight(permutation: &[usize], adj_matrix: &[Vec<usize>]) -> usize {
+        (0..permutation.len())
+            .map(|k| adj_matrix[permutation[k]][permutation[(k + 1) % permutation.len()]])
+            .sum()
+    }
+
+    fn asymmetric_matrix() -> Vec<Vec<usize>> {
+        vec![
+            vec![0, 3, 17, 25, 8, 40],
+            vec![21, 0, 4, 33, 12, 9],
+            vec![6, 30, 0, 2, 27, 15],
+            vec![14, 8, 35, 0, 5, 22],
+            vec![31, 19, 7, 26, 0, 1],
+            vec![2, 24, 13, 11, 38, 0],
+        ]
+    }
+
+    #[test]
+    fn invert_weight_matches_recomputation() {
+        let adj_matrix = asymmetric_matrix();
+        let permutation = vec![2, 0, 5, 3, 1, 4];
+        let weight = cycle_weight(&permutation, &adj_matrix);
+        for i in 0..6 {
+            for j in (i + 1)..6 {
+                if i == 0 && j == 5 {
+                    continue;
+                }
+                let mut moved = permutation.clone();
+                moved[i..=j].reverse();
+                let expected = cycle_weight(&moved, &adj_matrix);
+                assert_eq!(
+                    invert_weight(&permutation, &adj_matrix, i, j, weight, false),
+                    expected
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn insertion_weight_matches_recomputation() {
+        let adj_matrix = asymmetric_matrix();
+        let permutation = vec![2, 0, 5, 3, 1, 4];
+        let weight = cycle_weight(&permutation, &adj_matrix);
+        for i in 0..6 {
+            for j in i..6 {
+                for k in (0..6).filter(|&k| k != (i + 5) % 6 && (k < i || k > j)) {
+                    let mut moved = permutation.clone();
+                    insert_segment(&mut moved, i, j, k);
+                    let expected = cycle_weight(&moved, &adj_matrix);
+                    assert_eq!(
+                        insertion_weight(&permutation, &adj_matrix, i, j, k, weight),
+                        expected
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn insert_segment_moves_both_ways() {
+        let mut permutation = vec![0, 1, 2, 3, 4, 5];
+        insert_segment(&mut permutation, 1, 2, 4);
+        assert_eq!(permutation, [0, 3, 4, 1, 2, 5]);
+        let mut permutation = vec![0, 1, 2, 3, 4, 5];
+        insert_segment(&mut permutation, 3, 4, 0);
+        assert_eq!(permutation, [0, 3, 4, 1, 2, 5]);
+    }
+
+    #[test]
+    fn swap_weight_matches_recomputation() {
+        let adj_matrix = asymmetric_matrix();
+        let permutation = vec![2, 0, 5, 3, 1, 4];
+        let weight = cycle_weight(&permutation, &adj_matrix);
+        for i in 0..6 {
+            for j in (i + 1)..6 {
+                let mut moved = permutation.clone();
+                Move::Swap(i, j).apply(&mut moved);
+                let expected = cycle_weight(&moved, &adj_matrix);
+                assert_eq!(
+                    Move::Swap(i, j).weight(&permutation, &adj_matrix, weight, false),
+                    expected
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn reversed_insertion_weight_matches_recomputation() {
+        let adj_matrix = asymmetric_matrix();
+        let permutation = vec![2, 0, 5, 3, 1, 4];
+        let weight = cycle_weight(&permutation, &adj_matrix);
+        for i in 0..6 {
+            for j in i..6 {
+                for k in (0..6).filter(|&k| k != (i + 5) % 6 && (k < i || k > j)) {
+                    let mv = Move::ReversedInsertion { i, j, k };
+                    let mut moved = permutation.clone();
+                    mv.apply(&mut moved);
+                    let expected = cycle_weight(&moved, &adj_matrix);
+                    assert_eq!(
+                        mv.weight(&permutation, &adj_matrix, weight, false),
+                        expected
+                    );
+                }
+            }
+        }
+    }
+}
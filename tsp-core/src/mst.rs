@@ -0,0 +1,347 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+use crate::distance::Distance;
+
+/// Dense O(n²) Prim; `parent[v]` is the tree neighbour of `v` towards city 0.
+pub fn prim<D: Distance + ?Sized>(adj_matrix: &D, point_count: usize) -> Vec<usize> {
+    let mut parent: Vec<usize> = vec![usize::MAX; point_count];
+    let mut key: Vec<usize> = vec![usize::MAX; point_count];
+    let mut mst_set: Vec<bool> = vec![false; point_count];
+
+    key[0] = 0;
+
+    for _ in 0..(point_count - 1) {
+        let u = min_key(&key, &mst_set, point_count);
+        mst_set[u] = true;
+        for v in 0..point_count {
+            if u != v && !mst_set[v] && adj_matrix.dist(u, v) < key[v] {
+                parent[v] = u;
+                key[v] = adj_matrix.dist(u, v);
+            }
+        }
+    }
+
+    parent
+}
+
+fn min_key(key: &[usize], mst_set: &[bool], point_count: usize) -> usize {
+    let mut min = usize::MAX;
+    let mut min_index = 0;
+    for v in 0..point_count {
+        if !mst_set[v] && key[v] < min {
+            min = key[v];
+            min_index = v;
+        }
+    }
+    min_index
+}
+
+pub fn mst_weight<D: Distance + ?Sized>(parent: &[usize], adj_matrix: &D) -> usize {
+    let mut s: usize = 0;
+    for (i, &p) in parent.iter().enumerate().skip(1) {
+        s += adj_matrix.dist(i, p);
+    }
+    s
+}
+
+/// Nearest neighbours per city that let `candidate_prim` find the MST of the
+/// lab instances; seeding uses it with `candidate_lists`.
+pub const MST_CANDIDATES: usize = 10;
+
+/// Heap-based Prim over a candidate graph such as `candidate_lists` or a
+/// Delaunay triangulation, `j` in `candidates[i]` linking `i` and `j`:
+/// O(m log n) for m candidate edges, and no n² matrix needed. Gives the MST
+/// whenever the graph contains its edges, which `MST_CANDIDATES` nearest
+/// neighbours do on the lab instances (8 misses a few on pma343). Cities the
+/// graph leaves unreachable are joined through the shortest edge from the
+/// lowest of them to the tree.
+pub fn candidate_prim<D: Distance + ?Sized>(
+    adj_matrix: &D,
+    candidates: &[Vec<usize>],
+) -> Vec<usize> {
+    let point_count = candidates.len();
+    let mut graph: Vec<Vec<usize>> = vec![Vec::new(); point_count];
+    for (i, list) in candidates.iter().enumerate() {
+        for &j in list {
+            graph[i].push(j);
+            graph[j].push(i);
+        }
+    }
+    let mut parent: Vec<usize> = vec![usize::MAX; point_count];
+    let mut key: Vec<usize> = vec![usize::MAX; point_count];
+    let mut in_tree = BitSet::new(point_count);
+    let mut heap = BinaryHeap::new();
+    for root in 0..point_count {
+        if in_tree.contains(root) {
+            continue;
+        }
+        if root > 0 {
+            parent[root] = closest_in_tree(adj_matrix, &in_tree, root);
+        }
+        heap.push(Reverse((0, root)));
+        while let Some(Reverse((_, u))) = heap.pop() {
+            if !in_tree.insert(u) {
+                continue;
+            }
+            for &v in &graph[u] {
+                let d = adj_matrix.dist(u, v);
+                if !in_tree.contains(v) && d < key[v] {
+                    parent[v] = u;
+                    key[v] = d;
+                    heap.push(Reverse((d, v)));
+                }
+            }
+        }
+    }
+    parent
+}
+
+/// Kruskal with union–find over the same candidate graph as
+/// `candidate_prim`, returning the tree as parents towards city 0 as well.
+pub fn candidate_kruskal<D: Distance + ?Sized>(
+    adj_matrix: &D,
+    candidates: &[Vec<usize>],
+) -> Vec<usize> {
+    let point_count = candidates.len();
+    let mut edges: Vec<(usize, usize, usize)> = candidates
+        .iter()
+        .enumerate()
+        .flat_map(|(i, list)| {
+            list.iter()
+                .map(move |&j| (adj_matrix.dist(i, j), i.min(j), i.max(j)))
+        })
+        .collect();
+    edges.sort_unstable();
+    edges.dedup();
+    let mut sets = UnionFind::new(point_count);
+    let mut tree: Vec<Vec<usize>> = vec![Vec::new(); point_count];
+    for (_, a, b) in edges {
+        if sets.union(a, b) {
+            tree[a].push(b);
+            tree[b].push(a);
+        }
+    }
+    // Hang the forest from city 0, joining the trees like `candidate_prim`.
+    let mut parent: Vec<usize> = vec![usize::MAX; point_count];
+    let mut in_tree = BitSet::new(point_count);
+    let mut stack: Vec<usize> = Vec::new();
+    for root in 0..point_count {
+        if in_tree.contains(root) {
+            continue;
+        }
+        if root > 0 {
+            parent[root] = closest_in_tree(adj_matrix, &in_tree, root);
+        }
+        in_tree.insert(root);
+        stack.push(root);
+        while let Some(u) = stack.pop() {
+            for &v in &tree[u] {
+                if in_tree.insert(v) {
+                    parent[v] = u;
+                    stack.push(v);
+                }
+            }
+        }
+    }
+    parent
+}
+
+fn closest_in_tree<D: Distance + ?Sized>(adj_matrix: &D, in_tree: &BitSet, city: usize) -> usize {
+    (0..adj_matrix.point_count())
+        .filter(|&v| in_tree.contains(v))
+        .min_by_key(|&v| adj_matrix.dist(city, v))
+        .unwrap()
+}
+
+struct UnionFind {
+    parent: Vec<usize>,
+    size: Vec<usize>,
+}
+
+impl UnionFind {
+    fn new(len: usize) -> Self {
+        UnionFind {
+            parent: (0..len).collect(),
+            size: vec![1; len],
+        }
+    }
+
+    fn find(&mut self, mut x: usize) -> usize {
+        while self.parent[x] != x {
+            // Path halving.
+            self.parent[x] = self.parent[self.parent[x]];
+            x = self.parent[x];
+        }
+        x
+    }
+
+    /// Merges the sets of `a` and `b`; `false` if they already were one.
+    fn union(&mut self, a: usize, b: usize) -> bool {
+        let (mut a, mut b) = (self.find(a), self.find(b));
+        if a == b {
+            return false;
+        }
+        if self.size[a] < self.size[b] {
+            std::mem::swap(&mut a, &mut b);
+        }
+        self.parent[b] = a;
+        self.size[a] += self.size[b];
+        true
+    }
+}
+
+/// One bit per city.
+struct BitSet(Vec<u64>);
+
+impl BitSet {
+    fn new(len: usize) -> Self {
+        BitSet(vec![0; len.div_ceil(64)])
+    }
+
+    fn contains(&self, i: usize) -> bool {
+        self.0[i / 64] >> (i % 64) & 1 == 1
+    }
+
+    /// Adds `i`; `false` if it was there already.
+    fn insert(&mut self, i: usize) -> bool {
+        let fresh = !self.contains(i);
+        self.0[i / 64] |= 1 << (i % 64);
+        fresh
+    }
+}
+
+pub fn parent_to_adj_list(parent: &[usize]) -> Vec<Vec<usize>> {
+    let mut adj_list: Vec<Vec<usize>> = vec![Vec::new(); parent.len()];
+    for (u, v) in parent.iter().enumerate().skip(1) {
+        adj_list[u].push(*v);
+        adj_list[*v].push(u);
+    }
+    adj_list
+}
+
+/// Preorder of every component, starting each one at its lowest city.
+pub fn dfs(graph: &[Vec<usize>]) -> Vec<usize> {
+    let mut visited = BitSet::new(graph.len());
+    let mut traversal: Vec<usize> = Vec::with_capacity(graph.len());
+    for i in 0..graph.len() {
+        if !visited.contains(i) {
+            visit(graph, i, &mut visited, &mut traversal);
+        }
+    }
+    traversal
+}
+
+pub fn dfs_from_point(graph: &[Vec<usize>], start: usize) -> Vec<usize> {
+    let mut visited = BitSet::new(graph.len());
+    let mut traversal: Vec<usize> = Vec::with_capacity(graph.len());
+    visit(graph, start, &mut visited, &mut traversal);
+    traversal
+}
+
+fn visit(graph: &[Vec<usize>], start: usize, visited: &mut BitSet, traversal: &mut Vec<usize>) {
+    let mut stack: Vec<usize> = vec![start];
+    visited.insert(start);
+    while let Some(node) = stack.pop() {
+        traversal.push(node);
+        for &j in &graph[node] {
+            if visited.insert(j) {
+                stack.push(j);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prim_finds_path_tree() {
+        // Cities on a line: 0 - 1 - 2 - 3, plus a duplicate of city 3.
+        let xs = [0_i64, 4, 9, 15, 15];
+        let adj_matrix: Vec<Vec<usize>> = xs
+            .iter()
+            .map(|a| xs.iter().map(|b| (a - b).unsigned_abs() as usize).collect())
+            .collect();
+        let parent = prim(&adj_matrix, xs.len());
+        assert_eq!(&parent[1..], [0, 1, 2, 3]);
+        assert_eq!(mst_weight(&parent, &adj_matrix), 15);
+        let mst = parent_to_adj_list(&parent);
+        assert_eq!(dfs(&mst), [0, 1, 2, 3, 4]);
+        assert_eq!(dfs_from_point(&mst, 2), [2, 3, 4, 1, 0]);
+    }
+
+    #[test]
+    fn candidate_trees_match_dense_prim() {
+        use crate::{candidate_lists, instance_to_matrix, nearest_neighbours, Instance};
+        let instance = Instance::from_file("../l2/test_data/2.tsp").unwrap();
+        let adj_matrix = instance_to_matrix(&instance);
+        let expected = mst_weight(&prim(&adj_matrix, instance.dimension), &adj_matrix);
+        let candidates = candidate_lists(&instance, 8);
+        for parent in [
+            candidate_prim(&adj_matrix, &candidates),
+            candidate_kruskal(&adj_matrix, &candidates),
+        ] {
+            assert_eq!(parent[0], usize::MAX);
+            assert_eq!(mst_weight(&parent, &adj_matrix), expected);
+            let mut tour = dfs(&parent_to_adj_list(&parent));
+            tour.sort_unstable();
+            assert!(tour.into_iter().eq(0..instance.dimension));
+        }
+        // One neighbour each leaves the graph in pieces; they still get joined.
+        let candidates = nearest_neighbours(&adj_matrix, 1);
+        for parent in [
+            candidate_prim(&adj_matrix, &candidates),
+            candidate_kruskal(&adj_matrix, &candidates),
+        ] {
+            assert_eq!(
+                dfs_from_point(&parent_to_adj_list(&parent), 0).len(),
+                instance.dimension
+            );
+            assert!(mst_weight(&parent, &adj_matrix) >= expected);
+        }
+    }
+
+    #[test]
+    fn candidate_prim_scales_to_20k_cities() {
+        candidate_trees_on_random_points(20_000);
+    }
+
+    // Several seconds in debug: run with `cargo test -- --ignored`.
+    #[test]
+    #[ignore]
+    fn candidate_prim_scales_to_100k_cities() {
+        candidate_trees_on_random_points(100_000);
+    }
+
+    // Both candidate trees on uniform random points, without a matrix.
+    fn candidate_trees_on_random_points(point_count: usize) {
+        use crate::{candidate_lists, CoordinateDistance, Instance};
+        // Small LCG so the test needs no rand.
+        let mut state = 12345_u64;
+        let mut random = || {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (state >> 33) as f64 % 1_000_000.0
+        };
+        let instance = Instance {
+            dimension: point_count,
+            points: (0..point_count)
+                .map(|_| (random(), random(), 0.0))
+                .collect(),
+            ..Instance::default()
+        };
+        let candidates = candidate_lists(&instance, 8);
+        let distance = CoordinateDistance::new(&instance);
+        let parent = candidate_prim(&distance, &candidates);
+        assert_eq!(
+            mst_weight(&parent, &distance),
+            mst_weight(&candidate_kruskal(&distance, &candidates), &distance)
+        );
+        let mut tour = dfs_from_point(&parent_to_adj_list(&parent), 0);
+        tour.sort_unstable();
+        assert!(tour.into_iter().eq(0..point_count));
+    }
+}
@@ -0,0 +1,365 @@
+/// Tours above this many cities use a `TwoLevelList` rather than an
+/// `ArrayTour` for 2-opt style moves.
+pub const TWO_LEVEL_THRESHOLD: usize = 5_000;
+
+/// Cyclic tour that reverses paths in place, for 2-opt style moves.
+pub trait TourOrder {
+    fn next(&self, city: usize) -> usize;
+    fn prev(&self, city: usize) -> usize;
+    /// Whether `b` lies on the path from `a` forward to `c`, ends included.
+    fn between(&self, a: usize, b: usize, c: usize) -> bool;
+    /// Reverses the path from `from` forward to `to`.
+    fn flip(&mut self, from: usize, to: usize);
+    /// The cities in tour order.
+    fn order(&self) -> Vec<usize>;
+}
+
+fn between_keys<K: Ord>(a: K, b: K, c: K) -> bool {
+    if a <= c {
+        a <= b && b <= c
+    } else {
+        b >= a || b <= c
+    }
+}
+
+/// Array of cities with their positions. `reversed` reads it backwards, so a
+/// flip can reverse the shorter side of the tour: O(n) per flip.
+#[derive(Clone, Debug)]
+pub struct ArrayTour {
+    tour: Vec<usize>,
+    pos: Vec<usize>,
+    reversed: bool,
+}
+
+impl ArrayTour {
+    pub fn new(order: &[usize]) -> Self {
+        let mut pos = vec![0; order.len()];
+        for (i, &city) in order.iter().enumerate() {
+            pos[city] = i;
+        }
+        ArrayTour {
+            tour: order.to_vec(),
+            pos,
+            reversed: false,
+        }
+    }
+
+    fn key(&self, city: usize) -> usize {
+        if self.reversed {
+            self.tour.len() - 1 - self.pos[city]
+        } else {
+            self.pos[city]
+        }
+    }
+}
+
+impl TourOrder for ArrayTour {
+    fn next(&self, city: usize) -> usize {
+        let length = self.tour.len();
+        let p = self.pos[city];
+        if self.reversed {
+            self.tour[(p + length - 1) % length]
+        } else {
+            self.tour[(p + 1) % length]
+        }
+    }
+
+    fn prev(&self, city: usize) -> usize {
+        let length = self.tour.len();
+        let p = self.pos[city];
+        if self.reversed {
+            self.tour[(p + 1) % length]
+        } else {
+            self.tour[(p + length - 1) % length]
+        }
+    }
+
+    fn between(&self, a: usize, b: usize, c: usize) -> bool {
+        between_keys(self.key(a), self.key(b), self.key(c))
+    }
+
+    fn flip(&mut self, from: usize, to: usize) {
+        let length = self.tour.len();
+        let (mut i, mut j) = if self.reversed {
+            (self.pos[to], self.pos[from])
+        } else {
+            (self.pos[from], self.pos[to])
+        };
+        let mut inner = (j + length - i) % length + 1;
+        if 2 * inner > length {
+            // Reversing the rest gives the mirror image of the same tour.
+            (i, j) = ((j + 1) % length, (i + length - 1) % length);
+            inner = length - inner;
+            self.reversed = !self.reversed;
+        }
+        for _ in 0..inner / 2 {
+            self.tour.swap(i, j);
+            self.pos[self.tour[i]] = i;
+            self.pos[self.tour[j]] = j;
+            i = (i + 1) % length;
+            j = (j + length - 1) % length;
+        }
+    }
+
+    fn order(&self) -> Vec<usize> {
+        let mut order = self.tour.clone();
+        if self.reversed {
+            order.reverse();
+        }
+        order
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Segment {
+    cities: Vec<usize>,
+    reversed: bool,
+    rank: usize,
+}
+
+/// Two-level doubly-linked list: the tour is cut into segments of about √n
+/// cities, each with its own reversal bit, and a flip splits at most two
+/// segments and reverses the run of segments between them, O(√n) per flip.
+/// Segments get regrouped once splits have doubled their number.
+#[derive(Clone, Debug)]
+pub struct TwoLevelList {
+    segments: Vec<Segment>,
+    /// Segment ids in tour order; `segments[order[r]].rank == r`.
+    order: Vec<usize>,
+    segment_of: Vec<usize>,
+    /// Where a city sits in its segment's `cities`.
+    index: Vec<usize>,
+    group: usize,
+}
+
+impl TwoLevelList {
+    pub fn new(order: &[usize]) -> Self {
+        Self::with_group(order, ((order.len() as f64).sqrt() as usize).max(8))
+    }
+
+    /// Segments of `group` cities.
+    pub fn with_group(order: &[usize], group: usize) -> Self {
+        let mut list = TwoLevelList {
+            segments: Vec::new(),
+            order: Vec::new(),
+            segment_of: vec![0; order.len()],
+            index: vec![0; order.len()],
+            group: group.max(1),
+        };
+        list.regroup(order);
+        list
+    }
+
+    fn regroup(&mut self, order: &[usize]) {
+        self.segments.clear();
+        self.order.clear();
+        for (rank, chunk) in order.chunks(self.group).enumerate() {
+            for (i, &city) in chunk.iter().enumerate() {
+                self.segment_of[city] = rank;
+                self.index[city] = i;
+            }
+            self.segments.push(Segment {
+                cities: chunk.to_vec(),
+                reversed: false,
+                rank,
+            });
+            self.order.push(rank);
+        }
+    }
+
+    /// Position of `city` in its segment, in tour order.
+    fn offset(&self, city: usize) -> usize {
+        let segment = &self.segments[self.segment_of[city]];
+        if segment.reversed {
+            segment.cities.len() - 1 - self.index[city]
+        } else {
+            self.index[city]
+        }
+    }
+
+    fn at(&self, segment: usize, offset: usize) -> usize {
+        let segment = &self.segments[segment];
+        if segment.reversed {
+            segment.cities[segment.cities.len() - 1 - offset]
+        } else {
+            segment.cities[offset]
+        }
+    }
+
+    fn key(&self, city: usize) -> (usize, usize) {
+        (self.segments[self.segment_of[city]].rank, self.offset(city))
+    }
+
+    /// Moves the cities from `offset` on into a new segment right after
+    /// `segment`.
+    fn split(&mut self, segment: usize, offset: usize) {
+        if self.segments[segment].reversed {
+            let cities = &mut self.segments[segment].cities;
+            cities.reverse();
+            for (i, &city) in cities.iter().enumerate() {
+                self.index[city] = i;
+            }
+            self.segments[segment].reversed = false;
+        }
+        let tail = self.segments[segment].cities.split_off(offset);
+        let id = self.segments.len();
+        for (i, &city) in tail.iter().enumerate() {
+            self.segment_of[city] = id;
+            self.index[city] = i;
+        }
+        let rank = self.segments[segment].rank + 1;
+        self.segments.push(Segment {
+            cities: tail,
+            reversed: false,
+            rank,
+        });
+        self.order.insert(rank, id);
+        for (r, &s) in self.order.iter().enumerate().skip(rank) {
+            self.segments[s].rank = r;
+        }
+    }
+}
+
+impl TourOrder for TwoLevelList {
+    fn next(&self, city: usize) -> usize {
+        let segment = self.segment_of[city];
+        let offset = self.offset(city);
+        if offset + 1 < self.segments[segment].cities.len() {
+            self.at(segment, offset + 1)
+        } else {
+            let rank = (self.segments[segment].rank + 1) % self.order.len();
+            self.at(self.order[rank], 0)
+        }
+    }
+
+    fn prev(&self, city: usize) -> usize {
+        let segment = self.segment_of[city];
+        let offset = self.offset(city);
+        if offset > 0 {
+            self.at(segment, offset - 1)
+        } else {
+            let count = self.order.len();
+            let previous = self.order[(self.segments[segment].rank + count - 1) % count];
+            self.at(previous, self.segments[previous].cities.len() - 1)
+        }
+    }
+
+    fn between(&self, a: usize, b: usize, c: usize) -> bool {
+        between_keys(self.key(a), self.key(b), self.key(c))
+    }
+
+    fn flip(&mut self, from: usize, to: usize) {
+        if from == to {
+            return;
+        }
+        let segment = self.segment_of[from];
+        if segment == self.segment_of[to] && self.offset(from) < self.offset(to) {
+            let (i, j) = (
+                self.index[from].min(self.index[to]),
+                self.index[from].max(self.index[to]),
+            );
+            let cities = &mut self.segments[segment].cities;
+            cities[i..=j].reverse();
+            for (k, &city) in cities.iter().enumerate().take(j + 1).skip(i) {
+                self.index[city] = k;
+            }
+            return;
+        }
+        let offset = self.offset(from);
+        if offset > 0 {
+            self.split(self.segment_of[from], offset);
+        }
+        let segment = self.segment_of[to];
+        let offset = self.offset(to);
+        if offset + 1 < self.segments[segment].cities.len() {
+            self.split(segment, offset + 1);
+        }
+        let count = self.order.len();
+        let first = self.segments[self.segment_of[from]].rank;
+        let last = self.segments[self.segment_of[to]].rank;
+        let run = (last + count - first) % count + 1;
+        let (mut i, mut j) = (first, last);
+        for _ in 0..run / 2 {
+            self.order.swap(i, j);
+            i = (i + 1) % count;
+            j = (j + count - 1) % count;
+        }
+        for k in 0..run {
+            let r = (first + k) % count;
+            let segment = &mut self.segments[self.order[r]];
+            segment.rank = r;
+            segment.reversed = !segment.reversed;
+        }
+        if self.order.len() > 2 * self.segment_of.len().div_ceil(self.group) {
+            let order = self.order();
+            self.regroup(&order);
+        }
+    }
+
+    fn order(&self) -> Vec<usize> {
+        self.order
+            .iter()
+            .flat_map(|&segment| {
+                (0..self.segments[segment].cities.len()).map(move |offset| self.at(segment, offset))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Reverses the path from `from` to `to` in a plain cyclic vector.
+    fn flip_reference(tour: &mut [usize], from: usize, to: usize) {
+        let start = tour.iter().position(|&c| c == from).unwrap();
+        tour.rotate_left(start);
+        let end = tour.iter().position(|&c| c == to).unwrap();
+        tour[..=end].reverse();
+    }
+
+    fn check(tour: &impl TourOrder, reference: &[usize]) {
+        let length = reference.len();
+        for k in 0..length {
+            assert_eq!(tour.next(reference[k]), reference[(k + 1) % length]);
+            assert_eq!(tour.prev(reference[(k + 1) % length]), reference[k]);
+        }
+        let order = tour.order();
+        let start = order.iter().position(|&c| c == reference[0]).unwrap();
+        assert!(order[start..].iter().chain(&order[..start]).eq(reference));
+    }
+
+    #[test]
+    fn flips_match_reversing_a_vector() {
+        let length = 50;
+        let start: Vec<usize> = (0..length).map(|k| (k * 7) % length).collect();
+        let mut reference = start.clone();
+        let mut array = ArrayTour::new(&start);
+        let mut two_level = TwoLevelList::with_group(&start, 4);
+        // Small LCG so the test needs no rand.
+        let mut state = 12345_u64;
+        let mut random = |bound: usize| {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (state >> 33) as usize % bound
+        };
+        for _ in 0..2000 {
+            let (from, to) = (random(length), random(length));
+            flip_reference(&mut reference, from, to);
+            array.flip(from, to);
+            two_level.flip(from, to);
+            check(&array, &reference);
+            check(&two_level, &reference);
+            let (a, b, c) = (random(length), random(length), random(length));
+            let pos = |city| {
+                (reference.iter().position(|&x| x == city).unwrap() + length
+                    - reference.iter().position(|&x| x == a).unwrap())
+                    % length
+            };
+            let expected = pos(b) <= pos(c);
+            assert_eq!(array.between(a, b, c), expected);
+            assert_eq!(two_level.between(a, b, c), expected);
+        }
+    }
+}
@@ -0,0 +1,148 @@
+use std::error::Error;
+use std::fmt;
+use std::ops::Deref;
+
+use crate::distance::Distance;
+
+/// Visiting order of the cities, each index in `0..len` appearing exactly once.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Tour(Vec<usize>);
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum TourError {
+    Empty,
+    CityOutOfRange { city: usize, len: usize },
+    DuplicateCity { city: usize },
+}
+
+impl fmt::Display for TourError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TourError::Empty => write!(f, "tour is empty"),
+            TourError::CityOutOfRange { city, len } => {
+                write!(f, "city {city} out of range for a tour of {len} cities")
+            }
+            TourError::DuplicateCity { city } => write!(f, "city {city} is visited twice"),
+        }
+    }
+}
+
+impl Error for TourError {}
+
+impl Tour {
+    pub fn new(order: Vec<usize>) -> Result<Self, TourError> {
+        if order.is_empty() {
+            return Err(TourError::Empty);
+        }
+        let mut seen = vec![false; order.len()];
+        for &city in &order {
+            if city >= order.len() {
+                return Err(TourError::CityOutOfRange {
+                    city,
+                    len: order.len(),
+                });
+            }
+            if seen[city] {
+                return Err(TourError::DuplicateCity { city });
+            }
+            seen[city] = true;
+        }
+        Ok(Tour(order))
+    }
+
+    /// Closed length, including the edge from the last city back to the first.
+    pub fn length<D: Distance + ?Sized>(&self, adj_matrix: &D) -> usize {
+        permutation_weight(&self.0, adj_matrix)
+    }
+
+    pub fn into_vec(self) -> Vec<usize> {
+        self.0
+    }
+}
+
+impl Deref for Tour {
+    type Target = [usize];
+
+    fn deref(&self) -> &[usize] {
+        &self.0
+    }
+}
+
+impl TryFrom<Vec<usize>> for Tour {
+    type Error = TourError;
+
+    fn try_from(order: Vec<usize>) -> Result<Self, TourError> {
+        Tour::new(order)
+    }
+}
+
+pub fn permutation_weight<D: Distance + ?Sized>(permutation: &[usize], adj_matrix: &D) -> usize {
+    let mut s: usize = 0;
+    let mut prev = *permutation.last().unwrap();
+    for cur in permutation {
+        s += adj_matrix.dist(prev, *cur);
+        prev = *cur;
+    }
+    s
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{instance_to_matrix, Instance};
+
+    fn brute_force_length(instance: &Instance, order: &[usize]) -> usize {
+        let mut s = 0;
+        for k in 0..order.len() {
+            s += instance.distance(order[k], order[(k + 1) % order.len()]);
+        }
+        s
+    }
+
+    #[test]
+    fn length_matches_brute_force_on_test_data() {
+        for path in [
+            "../l1/test_data/xqf131.tsp",
+            "../l1/test_data/pma343.tsp",
+            "../l3/test_data/2.tsp",
+            "../l3/test_data/a.tsp",
+        ] {
+            let instance = Instance::from_file(path).unwrap();
+            let adj_matrix = instance_to_matrix(&instance);
+            let n = instance.dimension;
+            let identity: Vec<usize> = (0..n).collect();
+            let reversed: Vec<usize> = (0..n).rev().collect();
+            let strided: Vec<usize> = (0..n).map(|k| (k * 101 + 7) % n).collect();
+            let mut rotated = identity.clone();
+            rotated.rotate_left(n / 3);
+            for order in [identity, reversed, strided, rotated] {
+                let expected = brute_force_length(&instance, &order);
+                assert_eq!(permutation_weight(&order, &adj_matrix), expected, "{path}");
+                let tour = Tour::new(order).unwrap();
+                assert_eq!(tour.length(&adj_matrix), expected, "{path}");
+            }
+        }
+    }
+
+    #[test]
+    fn length_includes_closing_edge() {
+        let adj_matrix = vec![vec![0, 1, 10], vec![1, 0, 2], vec![10, 2, 0]];
+        let tour = Tour::new(vec![1, 2, 0]).unwrap();
+        assert_eq!(tour.length(&adj_matrix), 13);
+        assert_eq!(Tour::new(vec![0]).unwrap().length(&adj_matrix), 0);
+    }
+
+    #[test]
+    fn rejects_non_permutations() {
+        assert_eq!(Tour::new(Vec::new()), Err(TourError::Empty));
+        assert_eq!(
+            Tour::new(vec![0, 3, 1]),
+            Err(TourError::CityOutOfRange { city: 3, len: 3 })
+        );
+        assert_eq!(
+            Tour::new(vec![2, 0, 2]),
+            Err(TourError::DuplicateCity { city: 2 })
+        );
+        assert_eq!(&*Tour::try_from(vec![2, 0, 1]).unwrap(), [2, 0, 1]);
+    }
+}
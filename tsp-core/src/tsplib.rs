@@ -0,0 +1,814 @@
+use std::error::Error;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+use std::str::FromStr;
+
+/// Node coordinates; `z` is 0 for two-dimensional instances.
+pub type Point = (f64, f64, f64);
+
+/// Distance functions from the TSPLIB spec, picked by `EDGE_WEIGHT_TYPE`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum EdgeWeightType {
+    #[default]
+    Euc2d,
+    Euc3d,
+    Ceil2d,
+    Man2d,
+    Man3d,
+    Max2d,
+    Max3d,
+    Geo,
+    Att,
+    Explicit,
+}
+
+impl EdgeWeightType {
+    pub fn distance(&self, p1: &Point, p2: &Point) -> usize {
+        let dx = (p1.0 - p2.0).abs();
+        let dy = (p1.1 - p2.1).abs();
+        let dz = (p1.2 - p2.2).abs();
+        match self {
+            EdgeWeightType::Euc2d => nint((dx * dx + dy * dy).sqrt()),
+            EdgeWeightType::Euc3d => nint((dx * dx + dy * dy + dz * dz).sqrt()),
+            EdgeWeightType::Ceil2d => (dx * dx + dy * dy).sqrt().ceil() as usize,
+            EdgeWeightType::Man2d => nint(dx + dy),
+            EdgeWeightType::Man3d => nint(dx + dy + dz),
+            EdgeWeightType::Max2d => nint(dx).max(nint(dy)),
+            EdgeWeightType::Max3d => nint(dx).max(nint(dy)).max(nint(dz)),
+            EdgeWeightType::Geo => geo_distance(p1, p2),
+            EdgeWeightType::Att => {
+                let r = ((dx * dx + dy * dy) / 10.0).sqrt();
+                let t = nint(r);
+                if (t as f64) < r {
+                    t + 1
+                } else {
+                    t
+                }
+            }
+            EdgeWeightType::Explicit => {
+                panic!("EXPLICIT weights come from EDGE_WEIGHT_SECTION, not coordinates")
+            }
+        }
+    }
+}
+
+impl FromStr for EdgeWeightType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "EUC_2D" => Ok(EdgeWeightType::Euc2d),
+            "EUC_3D" => Ok(EdgeWeightType::Euc3d),
+            "CEIL_2D" => Ok(EdgeWeightType::Ceil2d),
+            "MAN_2D" => Ok(EdgeWeightType::Man2d),
+            "MAN_3D" => Ok(EdgeWeightType::Man3d),
+            "MAX_2D" => Ok(EdgeWeightType::Max2d),
+            "MAX_3D" => Ok(EdgeWeightType::Max3d),
+            "GEO" => Ok(EdgeWeightType::Geo),
+            "ATT" => Ok(EdgeWeightType::Att),
+            "EXPLICIT" => Ok(EdgeWeightType::Explicit),
+            _ => Err(format!("unsupported EDGE_WEIGHT_TYPE `{s}`")),
+        }
+    }
+}
+
+/// Layouts of `EDGE_WEIGHT_SECTION` for `EDGE_WEIGHT_TYPE : EXPLICIT`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EdgeWeightFormat {
+    Function,
+    FullMatrix,
+    UpperRow,
+    LowerRow,
+    UpperDiagRow,
+    LowerDiagRow,
+    UpperCol,
+    LowerCol,
+    UpperDiagCol,
+    LowerDiagCol,
+}
+
+impl EdgeWeightFormat {
+    /// Matrix cells in the order their weights appear in the section. The
+    /// column-wise layouts of a symmetric matrix are the row-wise layouts of
+    /// the opposite triangle.
+    fn positions(self, n: usize) -> Box<dyn Iterator<Item = (usize, usize)>> {
+        match self {
+            EdgeWeightFormat::Function => Box::new(std::iter::empty()),
+            EdgeWeightFormat::FullMatrix => {
+                Box::new((0..n).flat_map(move |i| (0..n).map(move |j| (i, j))))
+            }
+            EdgeWeightFormat::UpperRow | EdgeWeightFormat::LowerCol => {
+                Box::new((0..n).flat_map(move |i| ((i + 1)..n).map(move |j| (i, j))))
+            }
+            EdgeWeightFormat::LowerRow | EdgeWeightFormat::UpperCol => {
+                Box::new((0..n).flat_map(|i| (0..i).map(move |j| (i, j))))
+            }
+            EdgeWeightFormat::UpperDiagRow | EdgeWeightFormat::LowerDiagCol => {
+                Box::new((0..n).flat_map(move |i| (i..n).map(move |j| (i, j))))
+            }
+            EdgeWeightFormat::LowerDiagRow | EdgeWeightFormat::UpperDiagCol => {
+                Box::new((0..n).flat_map(|i| (0..=i).map(move |j| (i, j))))
+            }
+        }
+    }
+
+    fn to_matrix(self, n: usize, weights: &[usize]) -> Vec<Vec<usize>> {
+        let mut matrix = vec![vec![0; n]; n];
+        for ((i, j), &w) in self.positions(n).zip(weights) {
+            matrix[i][j] = w;
+            if self != EdgeWeightFormat::FullMatrix {
+                matrix[j][i] = w;
+            }
+        }
+        matrix
+    }
+}
+
+impl FromStr for EdgeWeightFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "FUNCTION" => Ok(EdgeWeightFormat::Function),
+            "FULL_MATRIX" => Ok(EdgeWeightFormat::FullMatrix),
+            "UPPER_ROW" => Ok(EdgeWeightFormat::UpperRow),
+            "LOWER_ROW" => Ok(EdgeWeightFormat::LowerRow),
+            "UPPER_DIAG_ROW" => Ok(EdgeWeightFormat::UpperDiagRow),
+            "LOWER_DIAG_ROW" => Ok(EdgeWeightFormat::LowerDiagRow),
+            "UPPER_COL" => Ok(EdgeWeightFormat::UpperCol),
+            "LOWER_COL" => Ok(EdgeWeightFormat::LowerCol),
+            "UPPER_DIAG_COL" => Ok(EdgeWeightFormat::UpperDiagCol),
+            "LOWER_DIAG_COL" => Ok(EdgeWeightFormat::LowerDiagCol),
+            _ => Err(format!("unsupported EDGE_WEIGHT_FORMAT `{s}`")),
+        }
+    }
+}
+
+fn nint(x: f64) -> usize {
+    (x + 0.5) as usize
+}
+
+fn geo_distance(p1: &Point, p2: &Point) -> usize {
+    // Coordinates are DDD.MM; TSPLIB truncates the degrees and uses its own
+    // values of pi and the earth radius, which the published optima depend on.
+    #[allow(clippy::approx_constant)]
+    const PI: f64 = 3.141592;
+    const RRR: f64 = 6378.388;
+    let radians = |x: f64| {
+        let deg = x.trunc();
+        PI * (deg + 5.0 * (x - deg) / 3.0) / 180.0
+    };
+    let (lat1, lon1) = (radians(p1.0), radians(p1.1));
+    let (lat2, lon2) = (radians(p2.0), radians(p2.1));
+    let q1 = (lon1 - lon2).cos();
+    let q2 = (lat1 - lat2).cos();
+    let q3 = (lat1 + lat2).cos();
+    (RRR * (0.5 * ((1.0 + q1) * q2 - (1.0 - q1) * q3)).acos() + 1.0) as usize
+}
+
+#[derive(Debug)]
+pub enum ParseError {
+    Io(io::Error),
+    MissingDimension {
+        line: usize,
+    },
+    BadNumber {
+        line: usize,
+        value: String,
+    },
+    UnknownWeightType {
+        line: usize,
+        value: String,
+    },
+    UnknownWeightFormat {
+        line: usize,
+        value: String,
+    },
+    CoordinateCountMismatch {
+        line: usize,
+        expected: usize,
+        found: usize,
+    },
+    WeightCountMismatch {
+        line: usize,
+        expected: usize,
+        found: usize,
+    },
+    TourLengthMismatch {
+        line: usize,
+        expected: usize,
+        found: usize,
+    },
+    NodeOutOfRange {
+        line: usize,
+        node: usize,
+    },
+    MissingEof {
+        line: usize,
+    },
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::Io(err) => write!(f, "{err}"),
+            ParseError::MissingDimension { line } => {
+                write!(f, "line {line}: DIMENSION is missing or zero")
+            }
+            ParseError::BadNumber { line, value } => {
+                write!(f, "line {line}: cannot parse `{value}` as a number")
+            }
+            ParseError::UnknownWeightType { line, value } => {
+                write!(f, "line {line}: unsupported EDGE_WEIGHT_TYPE `{value}`")
+            }
+            ParseError::UnknownWeightFormat { line, value } => {
+                write!(f, "line {line}: unsupported EDGE_WEIGHT_FORMAT `{value}`")
+            }
+            ParseError::CoordinateCountMismatch {
+                line,
+                expected,
+                found,
+            } => {
+                write!(
+                    f,
+                    "line {line}: expected {expected} coordinates, found {found}"
+                )
+            }
+            ParseError::WeightCountMismatch {
+                line,
+                expected,
+                found,
+            } => {
+                write!(
+                    f,
+                    "line {line}: expected {expected} edge weights, found {found}"
+                )
+            }
+            ParseError::TourLengthMismatch {
+                line,
+                expected,
+                found,
+            } => {
+                write!(
+                    f,
+                    "line {line}: expected {expected} tour nodes, found {found}"
+                )
+            }
+            ParseError::NodeOutOfRange { line, node } => {
+                write!(f, "line {line}: node {node} is out of range")
+            }
+            ParseError::MissingEof { line } => write!(f, "line {line}: missing EOF"),
+        }
+    }
+}
+
+impl Error for ParseError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ParseError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for ParseError {
+    fn from(err: io::Error) -> Self {
+        ParseError::Io(err)
+    }
+}
+
+/// Line iterator that remembers the 1-based number of the last line read.
+struct LineReader<R> {
+    lines: io::Lines<R>,
+    line: usize,
+}
+
+impl<R: BufRead> LineReader<R> {
+    fn next_line(&mut self) -> Result<Option<String>, ParseError> {
+        match self.lines.next() {
+            Some(line) => {
+                self.line += 1;
+                Ok(Some(line?))
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn number<T: FromStr>(&self, value: &str) -> Result<T, ParseError> {
+        value.parse().map_err(|_| ParseError::BadNumber {
+            line: self.line,
+            value: value.to_string(),
+        })
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Instance {
+    pub name: String,
+    pub problem_type: String,
+    pub comments: Vec<String>,
+    pub dimension: usize,
+    pub edge_weight_type: EdgeWeightType,
+    pub edge_weight_format: Option<EdgeWeightFormat>,
+    pub points: Vec<Point>,
+    /// Full distance matrix of an `EXPLICIT` instance.
+    pub weights: Option<Vec<Vec<usize>>>,
+}
+
+impl Instance {
+    pub fn from_file<P>(filename: P) -> Result<Self, ParseError>
+    where
+        P: AsRef<Path>,
+    {
+        let file = File::open(filename)?;
+        Self::parse(io::BufReader::new(file))
+    }
+
+    pub fn parse<R: BufRead>(reader: R) -> Result<Self, ParseError> {
+        let mut instance = Instance::default();
+        let mut reader = LineReader {
+            lines: reader.lines(),
+            line: 0,
+        };
+        let mut eof = false;
+        while let Some(line) = reader.next_line()? {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let (key, value) = match line.split_once(':') {
+                Some((key, value)) => (key.trim(), value.trim()),
+                None => (line, ""),
+            };
+            match key {
+                "NAME" => instance.name = value.to_string(),
+                "TYPE" => instance.problem_type = value.to_string(),
+                "COMMENT" => instance.comments.push(value.to_string()),
+                "DIMENSION" => instance.dimension = reader.number(value)?,
+                "EDGE_WEIGHT_TYPE" => {
+                    instance.edge_weight_type =
+                        value.parse().map_err(|_| ParseError::UnknownWeightType {
+                            line: reader.line,
+                            value: value.to_string(),
+                        })?
+                }
+                "EDGE_WEIGHT_FORMAT" => {
+                    instance.edge_weight_format =
+                        Some(value.parse().map_err(|_| ParseError::UnknownWeightFormat {
+                            line: reader.line,
+                            value: value.to_string(),
+                        })?)
+                }
+                "NODE_COORD_SECTION" => {
+                    instance.points = read_points(&mut reader, instance.dimension)?;
+                }
+                // Explicit instances may still ship coordinates for plotting.
+                "DISPLAY_DATA_SECTION" => {
+                    let points = read_points(&mut reader, instance.dimension)?;
+                    if instance.points.is_empty() {
+                        instance.points = points;
+                    }
+                }
+                "EDGE_WEIGHT_SECTION" => {
+                    if instance.dimension == 0 {
+                        return Err(ParseError::MissingDimension { line: reader.line });
+                    }
+                    let format = instance
+                        .edge_weight_format
+                        .unwrap_or(EdgeWeightFormat::FullMatrix);
+                    let count = format.positions(instance.dimension).count();
+                    // FUNCTION has no cells to list: its weights come from
+                    // coordinates, so a section for it would read nothing.
+                    if count == 0 {
+                        return Err(ParseError::UnknownWeightFormat {
+                            line: reader.line,
+                            value: "FUNCTION".to_string(),
+                        });
+                    }
+                    let mut weights: Vec<usize> = Vec::with_capacity(count);
+                    while weights.len() < count {
+                        let Some(line) = reader.next_line()? else {
+                            break;
+                        };
+                        for token in line.split_whitespace() {
+                            weights.push(reader.number(token)?);
+                        }
+                    }
+                    if weights.len() != count {
+                        return Err(ParseError::WeightCountMismatch {
+                            line: reader.line,
+                            expected: count,
+                            found: weights.len(),
+                        });
+                    }
+                    instance.weights = Some(format.to_matrix(instance.dimension, &weights));
+                }
+                "EOF" => {
+                    eof = true;
+                    break;
+                }
+                // Keys we do not use yet (CAPACITY, DISPLAY_DATA_TYPE, ...) are skipped.
+                _ => {}
+            }
+        }
+        if instance.dimension == 0 {
+            return Err(ParseError::MissingDimension { line: reader.line });
+        }
+        if !eof {
+            return Err(ParseError::MissingEof { line: reader.line });
+        }
+        if instance.edge_weight_type == EdgeWeightType::Explicit {
+            if instance.weights.is_none() {
+                return Err(ParseError::WeightCountMismatch {
+                    line: reader.line,
+                    expected: instance.dimension * instance.dimension,
+                    found: 0,
+                });
+            }
+        } else if instance.points.len() != instance.dimension {
+            return Err(ParseError::CoordinateCountMismatch {
+                line: reader.line,
+                expected: instance.dimension,
+                found: instance.points.len(),
+            });
+        }
+        Ok(instance)
+    }
+
+    pub fn distance(&self, i: usize, j: usize) -> usize {
+        match &self.weights {
+            Some(weights) => weights[i][j],
+            None => self
+                .edge_weight_type
+                .distance(&self.points[i], &self.points[j]),
+        }
+    }
+}
+
+/// Reads `dimension` lines of `id x y [z]`. A line without coordinates before
+/// that many (the next section or `EOF` of a short section) is a
+/// `CoordinateCountMismatch`. Input that runs out just ends the list, and
+/// `parse` then fails on the missing `EOF`.
+fn read_points<R: BufRead>(
+    reader: &mut LineReader<R>,
+    dimension: usize,
+) -> Result<Vec<Point>, ParseError> {
+    if dimension == 0 {
+        return Err(ParseError::MissingDimension { line: reader.line });
+    }
+    let mut points = Vec::with_capacity(dimension);
+    while points.len() < dimension {
+        let Some(line) = reader.next_line()? else {
+            break;
+        };
+        let tmp = line.split_whitespace().collect::<Vec<&str>>();
+        if tmp.len() < 3 {
+            return Err(ParseError::CoordinateCountMismatch {
+                line: reader.line,
+                expected: dimension,
+                found: points.len(),
+            });
+        }
+        let z = match tmp.get(3) {
+            Some(z) => reader.number(z)?,
+            None => 0.,
+        };
+        points.push((reader.number(tmp[1])?, reader.number(tmp[2])?, z));
+    }
+    Ok(points)
+}
+
+/// Reads the `TOUR_SECTION` of a `.tour` file as 0-based node indices.
+pub fn read_tour<P>(filename: P) -> Result<Vec<usize>, ParseError>
+where
+    P: AsRef<Path>,
+{
+    let file = File::open(filename)?;
+    parse_tour(io::BufReader::new(file))
+}
+
+pub fn parse_tour<R: BufRead>(reader: R) -> Result<Vec<usize>, ParseError> {
+    let mut reader = LineReader {
+        lines: reader.lines(),
+        line: 0,
+    };
+    let mut dimension = 0;
+    let mut tour: Vec<usize> = Vec::new();
+    while let Some(line) = reader.next_line()? {
+        let line = line.trim();
+        let (key, value) = match line.split_once(':') {
+            Some((key, value)) => (key.trim(), value.trim()),
+            None => (line, ""),
+        };
+        match key {
+            "DIMENSION" => dimension = reader.number(value)?,
+            "TOUR_SECTION" => {
+                if dimension == 0 {
+                    return Err(ParseError::MissingDimension { line: reader.line });
+                }
+                'section: while let Some(line) = reader.next_line()? {
+                    for token in line.split_whitespace() {
+                        // The section is terminated by -1.
+                        if token == "-1" {
+                            break 'section;
+                        }
+                        let node: usize = reader.number(token)?;
+                        if node == 0 || node > dimension {
+                            return Err(ParseError::NodeOutOfRange {
+                                line: reader.line,
+                                node,
+                            });
+                        }
+                        tour.push(node - 1);
+                    }
+                }
+                if tour.len() != dimension {
+                    return Err(ParseError::TourLengthMismatch {
+                        line: reader.line,
+                        expected: dimension,
+                        found: tour.len(),
+                    });
+                }
+            }
+            "EOF" => return Ok(tour),
+            _ => {}
+        }
+    }
+    Err(ParseError::MissingEof { line: reader.line })
+}
+
+/// Writes `tour` (0-based node indices) as a TSPLIB `.tour` file.
+pub fn write_tour<P>(filename: P, name: &str, comment: &str, tour: &[usize]) -> io::Result<()>
+where
+    P: AsRef<Path>,
+{
+    let mut file = io::BufWriter::new(File::create(filename)?);
+    writeln!(file, "NAME : {name}")?;
+    writeln!(file, "COMMENT : {comment}")?;
+    writeln!(file, "TYPE : TOUR")?;
+    writeln!(file, "DIMENSION : {}", tour.len())?;
+    writeln!(file, "TOUR_SECTION")?;
+    for node in tour {
+        writeln!(file, "{}", node + 1)?;
+    }
+    writeln!(file, "-1")?;
+    writeln!(file, "EOF")?;
+    file.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_header_and_coords() {
+        let data = "NAME : tiny\n\
+                    COMMENT : first\n\
+                    COMMENT : second\n\
+                    COMMENT : third\n\
+                    TYPE : TSP\n\
+                    DIMENSION : 3\n\
+                    EDGE_WEIGHT_TYPE : EUC_2D\n\
+                    NODE_COORD_SECTION\n\
+                    1 0 0\n\
+                    2 3 4\n\
+                    3 6.5 8\n\
+                    EOF\n";
+        let instance = Instance::parse(data.as_bytes()).unwrap();
+        assert_eq!(instance.name, "tiny");
+        assert_eq!(instance.problem_type, "TSP");
+        assert_eq!(instance.comments, ["first", "second", "third"]);
+        assert_eq!(instance.dimension, 3);
+        assert_eq!(instance.edge_weight_type, EdgeWeightType::Euc2d);
+        assert_eq!(instance.edge_weight_format, None);
+        assert_eq!(instance.points, [(0., 0., 0.), (3., 4., 0.), (6.5, 8., 0.)]);
+    }
+
+    #[test]
+    fn skips_unknown_keys() {
+        let data = "NAME: no_spaces\nTYPE: TSP\nDIMENSION: 2\nCAPACITY : 10\nEDGE_WEIGHT_TYPE: EUC_2D\nNODE_COORD_SECTION\n1 1 1\n2 2 2\nEOF\n";
+        let instance = Instance::parse(data.as_bytes()).unwrap();
+        assert_eq!(instance.name, "no_spaces");
+        assert_eq!(instance.points, [(1., 1., 0.), (2., 2., 0.)]);
+    }
+
+    #[test]
+    fn euclidean_distances_round_to_nearest() {
+        let data = "DIMENSION : 3\nEDGE_WEIGHT_TYPE : EUC_2D\nNODE_COORD_SECTION\n1 0 0\n2 3 4\n3 1 1\nEOF\n";
+        let instance = Instance::parse(data.as_bytes()).unwrap();
+        assert_eq!(instance.distance(0, 1), 5);
+        assert_eq!(instance.distance(0, 2), 1);
+        assert_eq!(
+            EdgeWeightType::Ceil2d.distance(&(0., 0., 0.), &(1., 1., 0.)),
+            2
+        );
+        assert_eq!(
+            EdgeWeightType::Euc3d.distance(&(0., 0., 0.), &(2., 3., 6.)),
+            7
+        );
+    }
+
+    #[test]
+    fn manhattan_and_maximum_distances() {
+        let (p1, p2) = ((0., 0., 0.), (3.4, 4.6, 1.));
+        assert_eq!(EdgeWeightType::Man2d.distance(&p1, &p2), 8);
+        assert_eq!(EdgeWeightType::Man3d.distance(&p1, &p2), 9);
+        assert_eq!(EdgeWeightType::Max2d.distance(&p1, &p2), 5);
+    }
+
+    #[test]
+    fn att_distance_rounds_up() {
+        // sqrt(100 / 10) = 3.16.., which ATT rounds up to 4.
+        assert_eq!(
+            EdgeWeightType::Att.distance(&(0., 0., 0.), &(10., 0., 0.)),
+            4
+        );
+        // sqrt(90 / 10) = 3 exactly.
+        assert_eq!(
+            EdgeWeightType::Att.distance(&(0., 0., 0.), &(9., 3., 0.)),
+            3
+        );
+    }
+
+    #[test]
+    fn geo_distance_matches_ulysses16() {
+        // First three nodes of ulysses16 and their published distances.
+        let data = "NAME : ulysses16.tsp\nTYPE : TSP\nDIMENSION : 4\nEDGE_WEIGHT_TYPE : GEO\nNODE_COORD_SECTION\n\
+                    1 38.24 20.42\n2 39.57 26.15\n3 40.56 25.32\n4 36.26 23.12\nEOF\n";
+        let instance = Instance::parse(data.as_bytes()).unwrap();
+        assert_eq!(instance.edge_weight_type, EdgeWeightType::Geo);
+        assert_eq!(instance.distance(0, 1), 509);
+        assert_eq!(instance.distance(0, 2), 501);
+        assert_eq!(instance.distance(0, 3), 312);
+    }
+
+    #[test]
+    fn rejects_unknown_weight_type() {
+        let data = "DIMENSION : 1\nEDGE_WEIGHT_TYPE : SPECIAL\nEOF\n";
+        assert!(matches!(
+            Instance::parse(data.as_bytes()),
+            Err(ParseError::UnknownWeightType { line: 2, .. })
+        ));
+    }
+
+    #[test]
+    fn reads_every_explicit_format() {
+        let expected = vec![
+            vec![0, 1, 2, 3],
+            vec![1, 0, 4, 5],
+            vec![2, 4, 0, 6],
+            vec![3, 5, 6, 0],
+        ];
+        for (format, section) in [
+            ("FULL_MATRIX", "0 1 2 3\n1 0 4 5\n2 4 0 6\n3 5 6 0"),
+            ("UPPER_ROW", "1 2 3\n4 5\n6"),
+            ("LOWER_ROW", "1\n2 4\n3 5 6"),
+            ("UPPER_DIAG_ROW", "0 1 2 3 0 4 5 0 6 0"),
+            ("LOWER_DIAG_ROW", "0\n1 0\n2 4 0\n3 5 6 0"),
+            ("UPPER_COL", "1 2 4\n3 5 6"),
+            ("LOWER_COL", "1 2 3 4 5 6"),
+            ("UPPER_DIAG_COL", "0 1 0 2 4 0 3 5 6 0"),
+            ("LOWER_DIAG_COL", "0 1 2 3\n0 4 5\n0 6\n0"),
+        ] {
+            let data = format!(
+                "NAME : m4\nTYPE : TSP\nDIMENSION : 4\nEDGE_WEIGHT_TYPE : EXPLICIT\n\
+                 EDGE_WEIGHT_FORMAT : {format}\nEDGE_WEIGHT_SECTION\n{section}\nEOF\n"
+            );
+            let instance = Instance::parse(data.as_bytes()).unwrap();
+            assert_eq!(instance.edge_weight_type, EdgeWeightType::Explicit);
+            assert_eq!(instance.weights.as_ref(), Some(&expected), "{format}");
+            assert_eq!(instance.distance(2, 3), 6);
+        }
+    }
+
+    #[test]
+    fn function_format_has_no_weight_section() {
+        let data = "DIMENSION : 2\nEDGE_WEIGHT_TYPE : EXPLICIT\nEDGE_WEIGHT_FORMAT : FUNCTION\n\
+                    EDGE_WEIGHT_SECTION\n7\nEOF\n";
+        assert!(matches!(
+            Instance::parse(data.as_bytes()),
+            Err(ParseError::UnknownWeightFormat { line: 4, .. })
+        ));
+    }
+
+    #[test]
+    fn explicit_instance_keeps_display_data() {
+        let data = "DIMENSION : 2\nEDGE_WEIGHT_TYPE : EXPLICIT\nEDGE_WEIGHT_FORMAT : UPPER_ROW\n\
+                    DISPLAY_DATA_TYPE : TWOD_DISPLAY\nEDGE_WEIGHT_SECTION\n 7\n\
+                    DISPLAY_DATA_SECTION\n1 1.5 2\n2 3 4\nEOF\n";
+        let instance = Instance::parse(data.as_bytes()).unwrap();
+        assert_eq!(instance.distance(1, 0), 7);
+        assert_eq!(instance.points, [(1.5, 2., 0.), (3., 4., 0.)]);
+    }
+
+    #[test]
+    fn short_weight_section_is_an_error() {
+        let data = "DIMENSION : 3\nEDGE_WEIGHT_TYPE : EXPLICIT\nEDGE_WEIGHT_FORMAT : UPPER_ROW\nEDGE_WEIGHT_SECTION\n1 2\n";
+        assert!(matches!(
+            Instance::parse(data.as_bytes()),
+            Err(ParseError::WeightCountMismatch {
+                line: 5,
+                expected: 3,
+                found: 2
+            })
+        ));
+    }
+
+    #[test]
+    fn missing_dimension_is_an_error() {
+        let data = "NAME : x\nEDGE_WEIGHT_TYPE : EUC_2D\nNODE_COORD_SECTION\n1 0 0\nEOF\n";
+        assert!(matches!(
+            Instance::parse(data.as_bytes()),
+            Err(ParseError::MissingDimension { line: 3 })
+        ));
+        assert!(matches!(
+            Instance::parse("NAME : empty\nEOF\n".as_bytes()),
+            Err(ParseError::MissingDimension { .. })
+        ));
+    }
+
+    #[test]
+    fn short_coordinate_section_is_an_error() {
+        let data = "DIMENSION : 3\nNODE_COORD_SECTION\n1 0 0\n2 1 1\nEOF\n";
+        let err = Instance::parse(data.as_bytes()).unwrap_err();
+        assert!(matches!(
+            err,
+            ParseError::CoordinateCountMismatch {
+                line: 5,
+                expected: 3,
+                found: 2
+            }
+        ));
+        assert_eq!(err.to_string(), "line 5: expected 3 coordinates, found 2");
+    }
+
+    #[test]
+    fn bad_number_reports_its_line() {
+        let data = "DIMENSION : 2\nNODE_COORD_SECTION\n1 0 0\n2 1 x1\nEOF\n";
+        let err = Instance::parse(data.as_bytes()).unwrap_err();
+        assert!(matches!(err, ParseError::BadNumber { line: 4, ref value } if value == "x1"));
+    }
+
+    #[test]
+    fn missing_eof_is_an_error() {
+        let data = "DIMENSION : 1\nNODE_COORD_SECTION\n1 0 0\n";
+        assert!(matches!(
+            Instance::parse(data.as_bytes()),
+            Err(ParseError::MissingEof { line: 3 })
+        ));
+    }
+
+    #[test]
+    fn missing_file_is_an_io_error() {
+        assert!(matches!(
+            Instance::from_file("test_data/no_such_file.tsp"),
+            Err(ParseError::Io(_))
+        ));
+    }
+
+    #[test]
+    fn parses_opt_tour() {
+        let data = "NAME : tiny.opt.tour\nCOMMENT : Optimum tour for tiny (12)\nTYPE : TOUR\n\
+                    DIMENSION : 4\nTOUR_SECTION\n1\n3 2\n4\n-1\nEOF\n";
+        assert_eq!(parse_tour(data.as_bytes()).unwrap(), [0, 2, 1, 3]);
+    }
+
+    #[test]
+    fn rejects_bad_tours() {
+        let short = "DIMENSION : 3\nTOUR_SECTION\n1\n2\n-1\nEOF\n";
+        assert!(matches!(
+            parse_tour(short.as_bytes()),
+            Err(ParseError::TourLengthMismatch {
+                expected: 3,
+                found: 2,
+                ..
+            })
+        ));
+        let out_of_range = "DIMENSION : 2\nTOUR_SECTION\n1\n3\n-1\nEOF\n";
+        assert!(matches!(
+            parse_tour(out_of_range.as_bytes()),
+            Err(ParseError::NodeOutOfRange { line: 4, node: 3 })
+        ));
+    }
+
+    #[test]
+    fn written_tour_reads_back() {
+        let path = std::env::temp_dir().join(format!("tsplib_{}.tour", std::process::id()));
+        write_tour(&path, "tiny.tour", "Length 12", &[3, 0, 2, 1]).unwrap();
+        assert_eq!(read_tour(&path).unwrap(), [3, 0, 2, 1]);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn atsp_full_matrix_keeps_direction() {
+        let data = "NAME : br3\nTYPE : ATSP\nDIMENSION : 3\nEDGE_WEIGHT_TYPE : EXPLICIT\n\
+                    EDGE_WEIGHT_FORMAT : FULL_MATRIX\nEDGE_WEIGHT_SECTION\n\
+                    9999 3 5\n4 9999 2\n8 1 9999\nEOF\n";
+        let instance = Instance::parse(data.as_bytes()).unwrap();
+        assert_eq!(instance.problem_type, "ATSP");
+        assert_eq!((instance.distance(0, 1), instance.distance(1, 0)), (3, 4));
+        assert_eq!((instance.distance(1, 2), instance.distance(2, 1)), (2, 1));
+    }
+}